@@ -0,0 +1,199 @@
+//! Render templates into async writers
+//!
+//! Enabling the `tokio` or `futures-io` features makes the derive macro also implement
+//! [`AsyncTemplate`]. The generated code renders into an in memory buffer which is
+//! written to the underlying writer once it fills up, this is checked after every piece
+//! of static content in the template so large templates are sent out as they render.
+//!
+//! Writers from tokio implement [`AsyncSink`] directly, writers from `futures-io` need to
+//! be wrapped in [`FuturesIo`].
+
+use std::fmt;
+use std::future::Future;
+use std::io;
+
+/// The buffered size at which output is written to the underlying writer
+pub const DEFAULT_FLUSH_SIZE: usize = 8 * 1024;
+
+/// A template which can be rendered into an async writer
+///
+/// This is implemented by the derive macro when the `tokio` or `futures-io` feature is enabled.
+pub trait AsyncTemplate: crate::Template {
+    /// Render the template into an async writer
+    ///
+    /// Errors from the writer are returned unchanged, formatting errors are
+    /// converted into an [`io::Error`]. Whether the future is [`Send`] depends on the
+    /// template, generic code which has to spawn it can use
+    /// [`write_async_send`](AsyncTemplate::write_async_send) instead.
+    fn write_async<S>(&self, sink: &mut S) -> impl Future<Output = io::Result<()>>
+    where
+        S: AsyncSink + ?Sized;
+
+    /// Render the template into an async writer with a future which is always [`Send`]
+    ///
+    /// The template is rendered completely before the returned future is polled, so unlike
+    /// [`write_async`](AsyncTemplate::write_async) the output is not written out in pieces
+    /// while it renders. The future does not borrow the template, only the sink.
+    fn write_async_send<'s, S>(
+        &self,
+        sink: &'s mut S,
+    ) -> impl Future<Output = io::Result<()>> + Send + 's
+    where
+        S: AsyncSink + ?Sized,
+    {
+        let rendered = self.render();
+        async move {
+            let rendered = rendered.map_err(io::Error::other)?;
+            sink.write_all(rendered.as_bytes()).await?;
+            sink.flush().await
+        }
+    }
+}
+
+/// An async destination for rendered output
+///
+/// This abstracts over the different `AsyncWrite` traits in the ecosystem. Sinks have to be
+/// [`Send`] so that rendering into them can move between threads.
+pub trait AsyncSink: Send {
+    /// Write the entire buffer
+    fn write_all(&mut self, buf: &[u8]) -> impl Future<Output = io::Result<()>> + Send;
+
+    /// Flush any output buffered by the sink
+    fn flush(&mut self) -> impl Future<Output = io::Result<()>> + Send;
+}
+
+#[cfg(feature = "tokio")]
+impl<W> AsyncSink for W
+where
+    W: tokio::io::AsyncWrite + Unpin + Send + ?Sized,
+{
+    async fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        tokio::io::AsyncWriteExt::write_all(self, buf).await
+    }
+
+    async fn flush(&mut self) -> io::Result<()> {
+        tokio::io::AsyncWriteExt::flush(self).await
+    }
+}
+
+/// Wraps a [`futures_io::AsyncWrite`] so that it can be used as an [`AsyncSink`]
+#[cfg(feature = "futures-io")]
+pub struct FuturesIo<W>(pub W);
+
+#[cfg(feature = "futures-io")]
+impl<W> AsyncSink for FuturesIo<W>
+where
+    W: futures_io::AsyncWrite + Unpin + Send,
+{
+    async fn write_all(&mut self, mut buf: &[u8]) -> io::Result<()> {
+        use std::pin::Pin;
+
+        while !buf.is_empty() {
+            let n = std::future::poll_fn(|cx| Pin::new(&mut self.0).poll_write(cx, buf)).await?;
+            if n == 0 {
+                return Err(io::ErrorKind::WriteZero.into());
+            }
+            buf = &buf[n..];
+        }
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> io::Result<()> {
+        std::future::poll_fn(|cx| std::pin::Pin::new(&mut self.0).poll_flush(cx)).await
+    }
+}
+
+/// The buffer that generated code renders into before writing to an [`AsyncSink`]
+pub struct AsyncBuffer<'a, S: ?Sized> {
+    buf: String,
+    flush_size: usize,
+    sink: &'a mut S,
+}
+
+impl<'a, S> AsyncBuffer<'a, S>
+where
+    S: AsyncSink + ?Sized,
+{
+    /// Create a buffer which should be flushed once it reaches `flush_size`
+    pub fn new(sink: &'a mut S, flush_size: usize) -> Self {
+        Self {
+            buf: String::with_capacity(flush_size),
+            flush_size,
+            sink,
+        }
+    }
+
+    /// Whether the buffer has reached its flush size
+    #[inline]
+    pub fn should_flush(&self) -> bool {
+        self.buf.len() >= self.flush_size
+    }
+
+    /// Write the buffered output to the sink
+    pub async fn flush(&mut self) -> io::Result<()> {
+        if !self.buf.is_empty() {
+            self.sink.write_all(self.buf.as_bytes()).await?;
+            self.buf.clear();
+        }
+        Ok(())
+    }
+
+    /// Write any remaining output and flush the sink
+    pub async fn finish(mut self) -> io::Result<()> {
+        self.flush().await?;
+        self.sink.flush().await
+    }
+
+    /// Append a string to the buffer
+    #[inline]
+    pub fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.buf.push_str(s);
+        Ok(())
+    }
+
+    /// Append formatted arguments to the buffer
+    #[inline]
+    pub fn write_fmt(&mut self, args: fmt::Arguments<'_>) -> fmt::Result {
+        fmt::Write::write_fmt(&mut self.buf, args)
+    }
+}
+
+impl<S: ?Sized> fmt::Write for AsyncBuffer<'_, S> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.buf.push_str(s);
+        Ok(())
+    }
+}
+
+/// An error that occurred while rendering asynchronously
+///
+/// This only exists inside generated code and is always converted into an [`io::Error`].
+#[derive(Debug)]
+pub enum RenderError {
+    /// A value failed to format
+    Fmt(fmt::Error),
+    /// The underlying writer failed
+    Io(io::Error),
+}
+
+impl From<fmt::Error> for RenderError {
+    fn from(err: fmt::Error) -> Self {
+        Self::Fmt(err)
+    }
+}
+
+impl From<io::Error> for RenderError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<RenderError> for io::Error {
+    fn from(err: RenderError) -> Self {
+        match err {
+            RenderError::Fmt(err) => io::Error::other(err),
+            RenderError::Io(err) => err,
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use std::fmt::{self, Write};
+
+use crate::Template;
+
+/// An object safe version of [`Template`]
+///
+/// [`Template::fmt`] is generic over its writer so `dyn Template` is not possible. This trait
+/// is implemented for every template and can be used as `Box<dyn DynTemplate>` to keep
+/// templates of different types together, for example the widgets of a dashboard that are
+/// chosen at runtime.
+///
+/// `dyn DynTemplate` implements [`Template`] itself so a boxed template can be used
+/// anywhere a template is expected, including inside of other templates. Its escaper is not
+/// known, so inside of a template which escapes its output it is escaped again unless it is
+/// marked with [`safe`](crate::DisplayExt::safe).
+///
+/// ```ignore
+/// let widgets: Vec<Box<dyn DynTemplate>> = vec![Box::new(Clock), Box::new(Weather { city })];
+/// let page = widgets.render()?;
+/// ```
+pub trait DynTemplate {
+    /// Returns the guessed mime type of the template if it has one
+    fn mime_str(&self) -> Option<&'static str>;
+
+    /// Render the template into a type erased writer
+    fn fmt_dyn(&self, writer: &mut dyn Write) -> fmt::Result;
+
+    /// Returns an estimate of the length of the rendered output in bytes
+    fn size_hint(&self) -> usize;
+
+    /// Render the template to a string
+    fn render(&self) -> Result<String, fmt::Error>;
+}
+
+impl<T: Template> DynTemplate for T {
+    fn mime_str(&self) -> Option<&'static str> {
+        Template::mime_str(self)
+    }
+
+    fn fmt_dyn(&self, writer: &mut dyn Write) -> fmt::Result {
+        Template::fmt(self, writer)
+    }
+
+    fn size_hint(&self) -> usize {
+        Template::size_hint(self)
+    }
+
+    fn render(&self) -> Result<String, fmt::Error> {
+        Template::render(self)
+    }
+}
+
+/// Forwards to a possibly unsized writer so that it can be passed as `&mut dyn Write`
+struct DynWriter<'a, W: ?Sized>(&'a mut W);
+
+impl<W: Write + ?Sized> Write for DynWriter<'_, W> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.write_str(s)
+    }
+
+    #[inline]
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        self.0.write_char(c)
+    }
+}
+
+macro_rules! impl_dyn_template {
+    ($($ty:ty),*) => {$(
+        impl Template for $ty {
+            fn mime_str(&self) -> Option<&'static str> {
+                DynTemplate::mime_str(self)
+            }
+
+            fn fmt(&self, writer: &mut (impl Write + ?Sized)) -> fmt::Result {
+                self.fmt_dyn(&mut DynWriter(writer))
+            }
+
+            fn size_hint(&self) -> usize {
+                DynTemplate::size_hint(self)
+            }
+        }
+    )*};
+}
+
+impl_dyn_template!(
+    dyn DynTemplate + '_,
+    dyn DynTemplate + Send + '_,
+    dyn DynTemplate + Send + Sync + '_
+);
+
+impl<T: Template + ?Sized> Template for Box<T> {
+    fn mime_str(&self) -> Option<&'static str> {
+        (**self).mime_str()
+    }
+
+    fn fmt(&self, writer: &mut (impl Write + ?Sized)) -> fmt::Result {
+        (**self).fmt(writer)
+    }
+
+    fn size_hint(&self) -> usize {
+        (**self).size_hint()
+    }
+}
+
+impl<T: Template + ?Sized> Template for &T {
+    fn mime_str(&self) -> Option<&'static str> {
+        (**self).mime_str()
+    }
+
+    fn fmt(&self, writer: &mut (impl Write + ?Sized)) -> fmt::Result {
+        (**self).fmt(writer)
+    }
+
+    fn size_hint(&self) -> usize {
+        (**self).size_hint()
+    }
+}
+
+/// `None` renders nothing and has no mime type
+impl<T: Template> Template for Option<T> {
+    fn mime_str(&self) -> Option<&'static str> {
+        self.as_ref().and_then(Template::mime_str)
+    }
+
+    fn fmt(&self, writer: &mut (impl Write + ?Sized)) -> fmt::Result {
+        match self {
+            Some(template) => template.fmt(writer),
+            None => Ok(()),
+        }
+    }
+
+    fn size_hint(&self) -> usize {
+        self.as_ref().map_or(0, Template::size_hint)
+    }
+}
+
+/// The templates are rendered one after another, the mime type is taken from the first one
+impl<T: Template> Template for Vec<T> {
+    fn mime_str(&self) -> Option<&'static str> {
+        self.first().and_then(Template::mime_str)
+    }
+
+    fn fmt(&self, writer: &mut (impl Write + ?Sized)) -> fmt::Result {
+        self.iter().try_for_each(|template| template.fmt(writer))
+    }
+
+    fn size_hint(&self) -> usize {
+        self.iter().map(Template::size_hint).sum()
+    }
+}
@@ -5,12 +5,145 @@
 //! trait. This is the essential escape format mechanism, the code generator will wrap anything in
 //! an expression with an [`Escaped`] this struct will conditionally use the specified [`Escaper`]
 //! to perform the desired escaping.
+//!
+//! ## Safe values
+//! Values whose type implements [`Safe`] are trusted to already be escaped, the generated code
+//! writes them out unchanged. This includes values marked with
+//! [`safe`](crate::DisplayExt::safe), and [`SafeString`]. Values which are only safe in one part
+//! of a template implement [`SafeIn`] for it instead, like the output of
+//! [`json_script`](crate::SerializeExt::json_script) which is only safe as code in a `<script>`.
+//! Templates embedded with [`Template::display`](crate::Template::display) are written unchanged
+//! where their escaper is [`AsStrictAs`] the one of the outer template, and escaped otherwise.
+
+use std::fmt::{Display, Write};
+use std::marker::PhantomData;
+
+use crate::extensions::DisplayTemplate;
+
+/// A marker for values which are already escaped and should be written without escaping
+///
+/// Implement this for your own types only if their output is always safe to embed,
+/// for example HTML built from other escaped values. Numbers and booleans are safe
+/// in any context.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` can not be escaped in this part of the template",
+    label = "this value must be marked as safe",
+    note = "values inside of a tag, or outside of strings in a script or style can not be escaped automatically",
+    note = "if the value is trusted mark it with `.safe()`"
+)]
+pub trait Safe: Display {}
+
+/// A marker for values which are already escaped for one part of a template
+///
+/// `C` is the [`Escaper`] used in that part, or [`Script`] for code inside of a `<script>`
+/// element which can not be escaped. Everything which is [`Safe`] is safe in every part.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` can not be written in this part of the template without escaping",
+    label = "this value is not safe here",
+    note = "values which are only safe in some parts of a template are escaped everywhere else",
+    note = "if the value is trusted mark it with `.safe()`"
+)]
+pub trait SafeIn<C>: Display {}
+
+impl<T: Safe + ?Sized, C> SafeIn<C> for T {}
+
+/// The part of a template with javascript code inside of a `<script>` element, see [`SafeIn`]
+pub struct Script;
+
+/// The escaper of every expression in a template, the derive macro implements this when
+/// all of the templates it is made of use the same escaper
+pub trait TemplateEscaper {
+    /// The escaper the output of the template is escaped with
+    type Escaper;
+}
+
+/// An escaper whose output is also safe where `E` is used
+///
+/// Every escaper is as strict as itself, [`HtmlAttr`] is as strict as [`Html`] as well.
+pub trait AsStrictAs<E> {}
 
-use std::fmt::Display;
+impl<E: Escaper> AsStrictAs<E> for E {}
+impl AsStrictAs<Html> for HtmlAttr {}
 
 /// A struct that marks a type as safe meaning it can skip escaping
 pub struct MarkedSafe<'a, T: ?Sized>(pub &'a T);
 
+impl<T: Display + ?Sized> Display for MarkedSafe<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// An owned string that is trusted to already be escaped
+///
+/// This is useful for keeping the output of a rendered template around
+/// to embed it in other templates later.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct SafeString(String);
+
+impl SafeString {
+    /// Mark the string as safe
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(value.into())
+    }
+
+    /// Returns the contents as a string slice
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the inner string
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl From<String> for SafeString {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl Display for SafeString {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl<T: Display + ?Sized> Safe for MarkedSafe<'_, T> {}
+impl<T, E> SafeIn<E> for DisplayTemplate<'_, T>
+where
+    T: crate::Template + TemplateEscaper + ?Sized,
+    T::Escaper: AsStrictAs<E>,
+{
+}
+impl Safe for SafeString {}
+impl<T: Safe + ?Sized> Safe for &T {}
+
+impl<T: TemplateEscaper + ?Sized> TemplateEscaper for &T {
+    type Escaper = T::Escaper;
+}
+
+impl<T: TemplateEscaper + ?Sized> TemplateEscaper for Box<T> {
+    type Escaper = T::Escaper;
+}
+
+impl<T: TemplateEscaper> TemplateEscaper for Option<T> {
+    type Escaper = T::Escaper;
+}
+
+impl<T: TemplateEscaper> TemplateEscaper for Vec<T> {
+    type Escaper = T::Escaper;
+}
+
+macro_rules! impl_safe {
+    ($($ty:ty),*) => {
+        $(impl Safe for $ty {})*
+    };
+}
+
+impl_safe!(bool, i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64);
+
 /// A wrapper type for conditionally escaping data
 ///
 /// Uses the [`Display`] implementation of `T` and the [`Escaper`] implementation
@@ -44,26 +177,63 @@ where
     }
 }
 
-impl<'a, T, E> Display for Escaped<'a, MarkedSafe<'a, T>, E>
+impl<T, E> Display for Escaped<'_, T, E>
 where
     T: Display + ?Sized,
     E: Escaper,
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.value.0.fmt(f)
+        self.escaper.fmt(self.value, f)
     }
 }
 
-impl<T, E> Display for Escaped<'_, T, E>
-where
-    T: Display + ?Sized,
-    E: Escaper,
-{
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.escaper.fmt(self.value, f)
+/// Chooses between escaping a value or writing it unchanged if it is safe for the escaper
+///
+/// This is used by the code generator, `(&Probe(&value, PhantomData::<E>)).escape(E)` resolves
+/// to [`EscapeSafe`] when the value is [`SafeIn<E>`](SafeIn) and to [`EscapeDisplay`] otherwise.
+#[doc(hidden)]
+pub struct Probe<'a, T: ?Sized, E>(pub &'a T, pub PhantomData<E>);
+
+#[doc(hidden)]
+pub trait EscapeSafe<'a, T: ?Sized, E> {
+    fn escape(&self, escaper: E) -> &'a T;
+}
+
+impl<'a, T: SafeIn<E> + ?Sized, E: Escaper> EscapeSafe<'a, T, E> for Probe<'a, T, E> {
+    #[inline]
+    fn escape(&self, _escaper: E) -> &'a T {
+        self.0
+    }
+}
+
+#[doc(hidden)]
+pub trait EscapeDisplay<'a, T: ?Sized, E> {
+    fn escape(&self, escaper: E) -> Escaped<'a, T, E>;
+}
+
+impl<'a, T: Display + ?Sized, E: Escaper> EscapeDisplay<'a, T, E> for &Probe<'a, T, E> {
+    #[inline]
+    fn escape(&self, escaper: E) -> Escaped<'a, T, E> {
+        Escaped::new(self.0, escaper)
     }
 }
 
+/// Used by the code generator where a value can not be escaped, this fails to compile
+/// unless the value is [`Safe`]
+#[doc(hidden)]
+#[inline]
+pub fn require_safe<T: Safe + ?Sized>(value: &T) -> &T {
+    value
+}
+
+/// Used by the code generator where a value can not be escaped, this fails to compile
+/// unless the value is safe in the part `C` of the template
+#[doc(hidden)]
+#[inline]
+pub fn require_safe_in<C, T: SafeIn<C> + ?Sized>(value: &T) -> &T {
+    value
+}
+
 /// An empty escaper it is a no-op
 pub struct Empty;
 /// An html escaper it uses [`html_escape`]
@@ -90,3 +260,282 @@ impl Escaper for Html {
         f.write_str(&res)
     }
 }
+
+/// An escaper for unquoted html attribute values, it uses [`html_escape`]
+pub struct HtmlAttr;
+
+/// An escaper for the contents of a javascript string
+///
+/// Everything other than ascii letters, digits, and `_,.-` is written as a `\x` or `\u`
+/// escape sequence so the output can not end the string, the script, or an attribute it is in.
+pub struct JsString;
+
+/// An escaper for a whole url in an html attribute
+///
+/// Urls with a scheme other than `http`, `https`, `mailto`, or `tel` are replaced with
+/// `#invalid-url` so that `javascript:` urls can not be injected. Characters which can not
+/// appear in a url are percent encoded.
+pub struct Url;
+
+/// An escaper for part of a url path, everything other than unreserved characters
+/// and `/` is percent encoded
+pub struct UrlPath;
+
+/// An escaper for part of a url query or fragment, everything other than unreserved
+/// characters is percent encoded
+pub struct UrlQuery;
+
+/// An escaper for the contents of a css string
+///
+/// Everything other than ascii letters and digits is written as a hex escape sequence.
+pub struct CssString;
+
+/// An escaper for css identifiers like class names, it follows `CSS.escape`
+pub struct CssIdent;
+
+/// An escaper for the contents of a json string
+///
+/// Besides the escapes json requires `<`, `>`, and `&` are written as unicode escapes
+/// so the output is also safe inside of an html `<script>`.
+pub struct Json;
+
+/// An escaper for xml text and quoted attribute values
+pub struct Xml;
+
+/// An escaper for LaTeX text, special characters are replaced with their commands
+pub struct Latex;
+
+/// An escaper for POSIX shell arguments
+///
+/// The value is always wrapped in single quotes so it is passed as a single argument.
+pub struct Shell;
+
+impl Escaper for HtmlAttr {
+    fn fmt<T: Display + ?Sized>(
+        &self,
+        value: &T,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        let value = value.to_string();
+        let res = html_escape::encode_unquoted_attribute(&value);
+        f.write_str(&res)
+    }
+}
+
+impl Escaper for JsString {
+    fn fmt<T: Display + ?Sized>(
+        &self,
+        value: &T,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        for c in value.to_string().chars() {
+            match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | ',' | '.' | '-' => f.write_char(c)?,
+                c if c.is_ascii() => write!(f, "\\x{:02x}", c as u32)?,
+                '\u{2028}' | '\u{2029}' => write!(f, "\\u{:04x}", c as u32)?,
+                c => f.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn is_unreserved(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | '~')
+}
+
+pub(crate) fn write_percent(c: char, f: &mut impl std::fmt::Write) -> std::fmt::Result {
+    for b in c.encode_utf8(&mut [0; 4]).bytes() {
+        write!(f, "%{b:02X}")?;
+    }
+    Ok(())
+}
+
+// percent encode every character that is not accepted by `keep`
+fn percent_encode(
+    value: &str,
+    f: &mut std::fmt::Formatter<'_>,
+    keep: impl Fn(char) -> bool,
+) -> std::fmt::Result {
+    for c in value.chars() {
+        match keep(c) {
+            true => f.write_char(c)?,
+            false => write_percent(c, f)?,
+        }
+    }
+    Ok(())
+}
+
+impl Escaper for Url {
+    fn fmt<T: Display + ?Sized>(
+        &self,
+        value: &T,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        let value = value.to_string();
+        let scheme = value
+            .find([':', '/', '?', '#'])
+            .filter(|i| value[*i..].starts_with(':'))
+            .map(|i| value[..i].trim().to_ascii_lowercase());
+        if let Some(scheme) = scheme {
+            if !matches!(scheme.as_str(), "http" | "https" | "mailto" | "tel") {
+                return f.write_str("#invalid-url");
+            }
+        }
+        for c in value.chars() {
+            match c {
+                '&' => f.write_str("&amp;")?,
+                c if is_unreserved(c) || "%:/?#[]@!$()*+,;=".contains(c) => f.write_char(c)?,
+                c => write_percent(c, f)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Escaper for UrlPath {
+    fn fmt<T: Display + ?Sized>(
+        &self,
+        value: &T,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        percent_encode(&value.to_string(), f, |c| is_unreserved(c) || c == '/')
+    }
+}
+
+impl Escaper for UrlQuery {
+    fn fmt<T: Display + ?Sized>(
+        &self,
+        value: &T,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        percent_encode(&value.to_string(), f, is_unreserved)
+    }
+}
+
+impl Escaper for CssString {
+    fn fmt<T: Display + ?Sized>(
+        &self,
+        value: &T,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        for c in value.to_string().chars() {
+            match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' => f.write_char(c)?,
+                c if c.is_ascii() => write!(f, "\\{:x} ", c as u32)?,
+                c => f.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Escaper for CssIdent {
+    fn fmt<T: Display + ?Sized>(
+        &self,
+        value: &T,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        let value = value.to_string();
+        for (i, c) in value.chars().enumerate() {
+            // identifiers can not start with a digit, or a hyphen and a digit
+            let leading_digit =
+                c.is_ascii_digit() && (i == 0 || (i == 1 && value.starts_with('-')));
+            match c {
+                '\0' => f.write_char('\u{FFFD}')?,
+                '\u{1}'..='\u{1f}' | '\u{7f}' => write!(f, "\\{:x} ", c as u32)?,
+                c if leading_digit => write!(f, "\\{:x} ", c as u32)?,
+                '-' if value == "-" => f.write_str("\\-")?,
+                c if c.is_ascii_alphanumeric() || matches!(c, '-' | '_') || !c.is_ascii() => {
+                    f.write_char(c)?
+                }
+                c => write!(f, "\\{c}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Escaper for Json {
+    fn fmt<T: Display + ?Sized>(
+        &self,
+        value: &T,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        for c in value.to_string().chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                '\n' => f.write_str("\\n")?,
+                '\r' => f.write_str("\\r")?,
+                '\t' => f.write_str("\\t")?,
+                c if c.is_control() || matches!(c, '<' | '>' | '&' | '\u{2028}' | '\u{2029}') => {
+                    write!(f, "\\u{:04x}", c as u32)?
+                }
+                c => f.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Escaper for Xml {
+    fn fmt<T: Display + ?Sized>(
+        &self,
+        value: &T,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        for c in value.to_string().chars() {
+            match c {
+                '&' => f.write_str("&amp;")?,
+                '<' => f.write_str("&lt;")?,
+                '>' => f.write_str("&gt;")?,
+                '"' => f.write_str("&quot;")?,
+                '\'' => f.write_str("&apos;")?,
+                c => f.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Escaper for Latex {
+    fn fmt<T: Display + ?Sized>(
+        &self,
+        value: &T,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        for c in value.to_string().chars() {
+            match c {
+                '\\' => f.write_str("\\textbackslash{}")?,
+                '^' => f.write_str("\\textasciicircum{}")?,
+                '~' => f.write_str("\\textasciitilde{}")?,
+                '<' => f.write_str("\\textless{}")?,
+                '>' => f.write_str("\\textgreater{}")?,
+                '&' | '%' | '$' | '#' | '_' | '{' | '}' => write!(f, "\\{c}")?,
+                c => f.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Escaper for Shell {
+    fn fmt<T: Display + ?Sized>(
+        &self,
+        value: &T,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_char('\'')?;
+        for part in value.to_string().split_inclusive('\'') {
+            match part.strip_suffix('\'') {
+                Some(part) => {
+                    f.write_str(part)?;
+                    f.write_str("'\\''")?;
+                }
+                None => f.write_str(part)?,
+            }
+        }
+        f.write_char('\'')
+    }
+}
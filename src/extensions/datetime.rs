@@ -0,0 +1,364 @@
+use std::borrow::Borrow;
+use std::fmt::{self, Display, Write};
+
+/// An extension for the date and time types of `chrono` and `time`, this
+/// requires either the `chrono` or the `time` feature
+///
+/// The formatting methods are not called `date` or `format` since both crates already
+/// have inherent methods with those names, which rust picks over a trait method.
+pub trait DateTimeExt: DateParts + Sized {
+    /// Create a struct that implements [`Display`] which formats the value
+    /// using a strftime style format string like `"%Y-%m-%d"`
+    ///
+    /// The supported specifiers are `%Y %y %m %d %e %H %I %M %S %f %j %p %b %B %a %A %u %w
+    /// %z %:z %s %F %T %R %D %n %t %%`, a `-` after the `%` removes padding like `%-d`.
+    /// A string literal format with an unsupported specifier is a compile error in templates,
+    /// any other format with one fails to render with a [`fmt::Error`].
+    fn strftime<'a>(&'a self, fmt: &'a str) -> Strftime<'a, &'a Self> {
+        Strftime { value: self, fmt }
+    }
+
+    /// Like [`strftime`](DateTimeExt::strftime) but the value is converted into `zone` first,
+    /// `zone` is a [`TimeZone`](chrono::TimeZone) for `chrono` or a `UtcOffset` for `time`
+    fn strftime_in<'a, Z>(&self, zone: Z, fmt: &'a str) -> Strftime<'a, Z::Output>
+    where
+        Z: InZone<Self>,
+    {
+        Strftime {
+            value: zone.convert(self),
+            fmt,
+        }
+    }
+
+    /// Create a struct that implements [`Display`] which writes the value as
+    /// an [RFC 3339](https://www.rfc-editor.org/rfc/rfc3339) timestamp
+    ///
+    /// Values without a timezone are written without an offset.
+    fn rfc3339(&self) -> Rfc3339<&Self> {
+        Rfc3339(self)
+    }
+
+    /// Create a struct that implements [`Display`] which writes how long ago the
+    /// value was compared to `now` like `3 hours ago` or `in 2 days`
+    ///
+    /// Only the largest unit is written, months are 30 days and years are 365 days.
+    fn relative<N: Borrow<Self>>(&self, now: N) -> Relative<'_, Self, N> {
+        Relative { value: self, now }
+    }
+}
+
+impl<T: DateParts> DateTimeExt for T {}
+
+/// The fields of a date and time needed to format it, created by [`DateParts::parts`]
+pub struct Parts {
+    year: i32,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8,
+    nanosecond: u32,
+    // days since monday
+    weekday: u8,
+    ordinal: u16,
+    // seconds east of utc, values without a timezone have none
+    offset: Option<i32>,
+    // values without a timezone are treated as utc
+    timestamp: i64,
+}
+
+/// Types which can be split into [`Parts`] to be formatted
+///
+/// This is implemented for the date and time types of `chrono` and `time` and is what
+/// [`DateTimeExt`] is implemented for.
+pub trait DateParts {
+    /// Split the value into the fields which are formatted
+    fn parts(&self) -> Parts;
+}
+
+impl<T: DateParts + ?Sized> DateParts for &T {
+    fn parts(&self) -> Parts {
+        (**self).parts()
+    }
+}
+
+/// A timezone which a value of type `D` can be converted into before it is formatted
+///
+/// This is what [`DateTimeExt::strftime_in`] takes as its zone, it is implemented for
+/// every [`TimeZone`](chrono::TimeZone) of `chrono` and for the `UtcOffset` of `time`.
+pub trait InZone<D> {
+    /// The value once it is in this timezone
+    type Output: DateParts;
+
+    /// Convert a value into this timezone
+    fn convert(&self, value: &D) -> Self::Output;
+}
+
+/// Formats a date with a strftime style format, created by [`DateTimeExt::strftime`]
+pub struct Strftime<'a, D> {
+    value: D,
+    fmt: &'a str,
+}
+
+impl<D: DateParts> Display for Strftime<'_, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_strftime(&self.value.parts(), self.fmt, f)
+    }
+}
+
+/// Writes a date as an RFC 3339 timestamp, created by [`DateTimeExt::rfc3339`]
+pub struct Rfc3339<D>(D);
+
+impl<D: DateParts> Display for Rfc3339<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let p = self.0.parts();
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            p.year, p.month, p.day, p.hour, p.minute, p.second
+        )?;
+        match p.nanosecond {
+            0 => {}
+            ns if ns % 1_000_000 == 0 => write!(f, ".{:03}", ns / 1_000_000)?,
+            ns if ns % 1_000 == 0 => write!(f, ".{:06}", ns / 1_000)?,
+            ns => write!(f, ".{ns:09}")?,
+        }
+        match p.offset {
+            Some(0) => f.write_char('Z'),
+            Some(offset) => write_offset(offset, true, f),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Writes how long ago a date was, created by [`DateTimeExt::relative`]
+pub struct Relative<'a, T, N> {
+    value: &'a T,
+    now: N,
+}
+
+impl<T: DateParts, N: Borrow<T>> Display for Relative<'_, T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const UNITS: [(u64, &str); 7] = [
+            (365 * 86400, "year"),
+            (30 * 86400, "month"),
+            (7 * 86400, "week"),
+            (86400, "day"),
+            (3600, "hour"),
+            (60, "minute"),
+            (1, "second"),
+        ];
+        let diff = self.now.borrow().parts().timestamp - self.value.parts().timestamp;
+        let secs = diff.unsigned_abs();
+        let Some((size, unit)) = UNITS.into_iter().find(|(size, _)| secs >= *size) else {
+            return f.write_str("just now");
+        };
+        let n = secs / size;
+        let plural = if n == 1 { "" } else { "s" };
+        match diff > 0 {
+            true => write!(f, "{n} {unit}{plural} ago"),
+            false => write!(f, "in {n} {unit}{plural}"),
+        }
+    }
+}
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+const WEEKDAYS: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+fn write_offset(offset: i32, colon: bool, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let sign = if offset < 0 { '-' } else { '+' };
+    let minutes = offset.unsigned_abs() / 60;
+    let sep = if colon { ":" } else { "" };
+    write!(f, "{sign}{:02}{sep}{:02}", minutes / 60, minutes % 60)
+}
+
+fn write_strftime(p: &Parts, fmt: &str, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let mut chars = fmt.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            f.write_char(c)?;
+            continue;
+        }
+        let mut spec = chars.next().ok_or(fmt::Error)?;
+        let pad = spec != '-';
+        if !pad {
+            spec = chars.next().ok_or(fmt::Error)?;
+        }
+        let num = |f: &mut fmt::Formatter<'_>, n: i64, width: usize| match pad {
+            true => write!(f, "{n:0width$}"),
+            false => write!(f, "{n}"),
+        };
+        let hour12 = match p.hour % 12 {
+            0 => 12,
+            h => h,
+        };
+        match spec {
+            'Y' => num(f, p.year.into(), 4)?,
+            'y' => num(f, p.year.rem_euclid(100).into(), 2)?,
+            'm' => num(f, p.month.into(), 2)?,
+            'd' => num(f, p.day.into(), 2)?,
+            'e' if pad => write!(f, "{:>2}", p.day)?,
+            'e' => write!(f, "{}", p.day)?,
+            'H' => num(f, p.hour.into(), 2)?,
+            'I' => num(f, hour12.into(), 2)?,
+            'M' => num(f, p.minute.into(), 2)?,
+            'S' => num(f, p.second.into(), 2)?,
+            'f' => write!(f, "{:09}", p.nanosecond)?,
+            'j' => num(f, p.ordinal.into(), 3)?,
+            'p' => f.write_str(if p.hour < 12 { "AM" } else { "PM" })?,
+            'b' | 'h' => f.write_str(&MONTHS[usize::from(p.month) - 1][..3])?,
+            'B' => f.write_str(MONTHS[usize::from(p.month) - 1])?,
+            'a' => f.write_str(&WEEKDAYS[usize::from(p.weekday)][..3])?,
+            'A' => f.write_str(WEEKDAYS[usize::from(p.weekday)])?,
+            'u' => write!(f, "{}", p.weekday + 1)?,
+            'w' => write!(f, "{}", (p.weekday + 1) % 7)?,
+            'z' => {
+                if let Some(offset) = p.offset {
+                    write_offset(offset, false, f)?
+                }
+            }
+            ':' => match (chars.next(), p.offset) {
+                (Some('z'), Some(offset)) => write_offset(offset, true, f)?,
+                (Some('z'), None) => {}
+                _ => return Err(fmt::Error),
+            },
+            's' => write!(f, "{}", p.timestamp)?,
+            'F' => write_strftime(p, "%Y-%m-%d", f)?,
+            'T' => write_strftime(p, "%H:%M:%S", f)?,
+            'R' => write_strftime(p, "%H:%M", f)?,
+            'D' => write_strftime(p, "%m/%d/%y", f)?,
+            'n' => f.write_char('\n')?,
+            't' => f.write_char('\t')?,
+            '%' => f.write_char('%')?,
+            _ => return Err(fmt::Error),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "chrono")]
+mod chrono_impl {
+    use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone, Timelike};
+
+    use super::{DateParts, InZone, Parts};
+
+    fn naive_parts(value: &NaiveDateTime, offset: Option<i32>, timestamp: i64) -> Parts {
+        Parts {
+            year: value.year(),
+            month: value.month() as u8,
+            day: value.day() as u8,
+            hour: value.hour() as u8,
+            minute: value.minute() as u8,
+            second: value.second() as u8,
+            // leap seconds are stored as an extra second of nanoseconds
+            nanosecond: value.nanosecond() % 1_000_000_000,
+            weekday: value.weekday().num_days_from_monday() as u8,
+            ordinal: value.ordinal() as u16,
+            offset,
+            timestamp,
+        }
+    }
+
+    impl<Tz: TimeZone> DateParts for chrono::DateTime<Tz> {
+        fn parts(&self) -> Parts {
+            let offset = self.offset().fix().local_minus_utc();
+            naive_parts(&self.naive_local(), Some(offset), self.timestamp())
+        }
+    }
+
+    impl DateParts for NaiveDateTime {
+        fn parts(&self) -> Parts {
+            naive_parts(self, None, self.and_utc().timestamp())
+        }
+    }
+
+    impl DateParts for NaiveDate {
+        fn parts(&self) -> Parts {
+            self.and_time(NaiveTime::MIN).parts()
+        }
+    }
+
+    impl<Tz: TimeZone, Z: TimeZone> InZone<chrono::DateTime<Tz>> for Z {
+        type Output = chrono::DateTime<Z>;
+
+        fn convert(&self, value: &chrono::DateTime<Tz>) -> Self::Output {
+            value.with_timezone(self)
+        }
+    }
+}
+
+#[cfg(feature = "time")]
+mod time_impl {
+    use time::{Date, OffsetDateTime, PrimitiveDateTime, UtcOffset};
+
+    use super::{DateParts, InZone, Parts};
+
+    fn primitive_parts(value: &PrimitiveDateTime, offset: Option<i32>, timestamp: i64) -> Parts {
+        Parts {
+            year: value.year(),
+            month: value.month().into(),
+            day: value.day(),
+            hour: value.hour(),
+            minute: value.minute(),
+            second: value.second(),
+            nanosecond: value.nanosecond(),
+            weekday: value.weekday().number_days_from_monday(),
+            ordinal: value.ordinal(),
+            offset,
+            timestamp,
+        }
+    }
+
+    impl DateParts for OffsetDateTime {
+        fn parts(&self) -> Parts {
+            let local = PrimitiveDateTime::new(self.date(), self.time());
+            primitive_parts(
+                &local,
+                Some(self.offset().whole_seconds()),
+                self.unix_timestamp(),
+            )
+        }
+    }
+
+    impl DateParts for PrimitiveDateTime {
+        fn parts(&self) -> Parts {
+            primitive_parts(self, None, self.assume_utc().unix_timestamp())
+        }
+    }
+
+    impl DateParts for Date {
+        fn parts(&self) -> Parts {
+            self.midnight().parts()
+        }
+    }
+
+    impl InZone<OffsetDateTime> for UtcOffset {
+        type Output = OffsetDateTime;
+
+        fn convert(&self, value: &OffsetDateTime) -> Self::Output {
+            value.to_offset(*self)
+        }
+    }
+}
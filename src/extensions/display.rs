@@ -1,17 +1,69 @@
 use std::fmt::Display;
 
+use super::text::{
+    Capitalize, Filtered, Indent, Linebreaks, Lowercase, Replace, Slugify, StripTags, Title,
+    Trim, Truncate, UrlEncode, Uppercase, WordWrap,
+};
 use crate::escaping::MarkedSafe;
 
 /// An extension for types that implement [`Display`]
+///
+/// Apart from `safe` every method returns an adapter which transforms the display output
+/// as it is written, so the value is never collected into a [`String`] first. This trait is
+/// in scope for every template, so its methods are named differently from the methods of
+/// [`str`] and [`String`] which would otherwise be shadowed.
 pub trait DisplayExt {
     /// Mark the value as safe from escaping
     fn safe(&self) -> MarkedSafe<'_, Self>;
 
     /// Convert the display output to lowercase
-    fn lowercase(&self) -> String;
+    fn lowercase(&self) -> Filtered<'_, Self, Lowercase>;
 
     /// Convert the display output to uppercase
-    fn uppercase(&self) -> String;
+    fn uppercase(&self) -> Filtered<'_, Self, Uppercase>;
+
+    /// Cut the display output after `len` characters, `...` is added if anything was removed
+    fn truncate_chars(&self, len: usize) -> Filtered<'_, Self, Truncate>;
+
+    /// Break the display output into lines of at most `width` characters
+    ///
+    /// Lines are only broken between words so a single long word may still exceed the width,
+    /// runs of whitespace between words are written as a single space.
+    fn wordwrap(&self, width: usize) -> Filtered<'_, Self, WordWrap>;
+
+    /// Indent every line after the first by `width` spaces, blank lines are left empty
+    fn indent(&self, width: usize) -> Filtered<'_, Self, Indent>;
+
+    /// Uppercase the first letter of every word and lowercase the rest
+    fn title(&self) -> Filtered<'_, Self, Title>;
+
+    /// Uppercase the first character and lowercase the rest
+    fn capitalize(&self) -> Filtered<'_, Self, Capitalize>;
+
+    /// Remove leading and trailing whitespace from the display output
+    fn trim_text(&self) -> Filtered<'_, Self, Trim>;
+
+    /// Replace every occurrence of `from` in the display output with `to`
+    fn replace_text<'a>(&'a self, from: &'a str, to: &'a str) -> Filtered<'a, Self, Replace<'a>>;
+
+    /// Convert the display output into html paragraphs
+    ///
+    /// A single newline becomes a `<br>` and blank lines separate paragraphs. The text
+    /// is html escaped and the result is written unchanged in html text and quoted
+    /// attributes, anywhere else it is escaped again.
+    fn linebreaks(&self) -> Filtered<'_, Self, Linebreaks>;
+
+    /// Remove anything that looks like an html tag from the display output
+    ///
+    /// This is not a sanitizer, the remaining text is escaped like any other value.
+    fn striptags(&self) -> Filtered<'_, Self, StripTags>;
+
+    /// Lowercase the display output and join the words with `-`, anything which
+    /// is not a letter or digit is treated as a separator
+    fn slugify(&self) -> Filtered<'_, Self, Slugify>;
+
+    /// Percent encode every character except ascii letters, digits and `-._~`
+    fn urlencode(&self) -> Filtered<'_, Self, UrlEncode>;
 }
 
 impl<T> DisplayExt for T
@@ -22,19 +74,63 @@ where
         MarkedSafe(self)
     }
 
-    fn lowercase(&self) -> String {
-        self.to_string().to_lowercase()
+    fn lowercase(&self) -> Filtered<'_, Self, Lowercase> {
+        Filtered::new(self, Lowercase)
+    }
+
+    fn uppercase(&self) -> Filtered<'_, Self, Uppercase> {
+        Filtered::new(self, Uppercase)
+    }
+
+    fn truncate_chars(&self, len: usize) -> Filtered<'_, Self, Truncate> {
+        Filtered::new(self, Truncate::new(len))
+    }
+
+    fn wordwrap(&self, width: usize) -> Filtered<'_, Self, WordWrap> {
+        Filtered::new(self, WordWrap::new(width))
+    }
+
+    fn indent(&self, width: usize) -> Filtered<'_, Self, Indent> {
+        Filtered::new(self, Indent::new(width))
+    }
+
+    fn title(&self) -> Filtered<'_, Self, Title> {
+        Filtered::new(self, Title::new())
+    }
+
+    fn capitalize(&self) -> Filtered<'_, Self, Capitalize> {
+        Filtered::new(self, Capitalize::new())
+    }
+
+    fn trim_text(&self) -> Filtered<'_, Self, Trim> {
+        Filtered::new(self, Trim::new())
+    }
+
+    fn replace_text<'a>(&'a self, from: &'a str, to: &'a str) -> Filtered<'a, Self, Replace<'a>> {
+        Filtered::new(self, Replace::new(from, to))
+    }
+
+    fn linebreaks(&self) -> Filtered<'_, Self, Linebreaks> {
+        Filtered::new(self, Linebreaks::new())
+    }
+
+    fn striptags(&self) -> Filtered<'_, Self, StripTags> {
+        Filtered::new(self, StripTags::new())
+    }
+
+    fn slugify(&self) -> Filtered<'_, Self, Slugify> {
+        Filtered::new(self, Slugify::new())
     }
 
-    fn uppercase(&self) -> String {
-        self.to_string().to_uppercase()
+    fn urlencode(&self) -> Filtered<'_, Self, UrlEncode> {
+        Filtered::new(self, UrlEncode)
     }
 }
 
 /// Wrapper around a template which implements [`Display`](std::fmt::Display)
 pub struct DisplayTemplate<'a, T: ?Sized>(pub &'a T);
 
-impl<'a, T: crate::Template> std::fmt::Display for DisplayTemplate<'a, T> {
+impl<'a, T: crate::Template + ?Sized> std::fmt::Display for DisplayTemplate<'a, T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         self.0.fmt(f)
     }
@@ -0,0 +1,131 @@
+use std::fmt::Display;
+use std::iter::{Cycle, Take, Zip};
+
+/// An extension for collections which can be iterated by reference like [`Vec`] or slices
+pub trait IterExt {
+    /// Create a struct that implements [`Display`] which writes every item
+    /// with `sep` between them, nothing is collected until it is displayed
+    ///
+    /// This is not named `join` so it does not hide [`join`](slice::join) on slices.
+    fn join_with<'a, S>(&'a self, sep: S) -> Join<'a, Self, S>
+    where
+        &'a Self: IntoIterator,
+        S: Display,
+    {
+        Join { items: self, sep }
+    }
+
+    /// Iterate over the items in groups of `size`, the last group may be smaller
+    ///
+    /// # Panics
+    /// Panics if `size` is 0
+    fn batch<'a>(&'a self, size: usize) -> Batch<<&'a Self as IntoIterator>::IntoIter>
+    where
+        &'a Self: IntoIterator,
+    {
+        assert!(size > 0, "batch size must be greater than 0");
+        Batch {
+            iter: self.into_iter(),
+            size,
+        }
+    }
+
+    /// Group the items by the key returned from `key`
+    ///
+    /// Groups are in the order their key first appears and keep the order of their items,
+    /// the items do not need to be sorted.
+    ///
+    /// Every key is compared with the keys of the groups found before it, so this takes
+    /// time proportional to the number of items times the number of groups. Collections
+    /// with many distinct keys are better grouped before rendering.
+    fn group_by<'a, K, F>(
+        &'a self,
+        mut key: F,
+    ) -> std::vec::IntoIter<(K, Vec<<&'a Self as IntoIterator>::Item>)>
+    where
+        &'a Self: IntoIterator,
+        K: PartialEq,
+        F: FnMut(&<&'a Self as IntoIterator>::Item) -> K,
+    {
+        let mut groups: Vec<(K, Vec<_>)> = Vec::new();
+        for item in self {
+            let k = key(&item);
+            match groups.iter_mut().find(|(group, _)| *group == k) {
+                Some((_, items)) => items.push(item),
+                None => groups.push((k, vec![item])),
+            }
+        }
+        groups.into_iter()
+    }
+
+    /// Pair every item with the next of `values`, starting over once they run out
+    ///
+    /// This is useful for alternating classes like `cycle_with(["odd", "even"])`,
+    /// nothing is produced if `values` is empty.
+    fn cycle_with<'a, C>(
+        &'a self,
+        values: C,
+    ) -> Zip<<&'a Self as IntoIterator>::IntoIter, Cycle<C::IntoIter>>
+    where
+        &'a Self: IntoIterator,
+        C: IntoIterator,
+        C::IntoIter: Clone,
+    {
+        self.into_iter().zip(values.into_iter().cycle())
+    }
+
+    /// Iterate over at most the first `n` items
+    fn first_n<'a>(&'a self, n: usize) -> Take<<&'a Self as IntoIterator>::IntoIter>
+    where
+        &'a Self: IntoIterator,
+    {
+        self.into_iter().take(n)
+    }
+}
+
+impl<T> IterExt for T
+where
+    T: ?Sized,
+    for<'a> &'a T: IntoIterator,
+{
+}
+
+pub struct Join<'a, T: ?Sized, S> {
+    items: &'a T,
+    sep: S,
+}
+
+impl<'a, T, S> Display for Join<'a, T, S>
+where
+    T: ?Sized,
+    &'a T: IntoIterator,
+    <&'a T as IntoIterator>::Item: Display,
+    S: Display,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, item) in self.items.into_iter().enumerate() {
+            if i > 0 {
+                self.sep.fmt(f)?;
+            }
+            item.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct Batch<I> {
+    iter: I,
+    size: usize,
+}
+
+impl<I: Iterator> Iterator for Batch<I> {
+    type Item = Vec<I::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let batch: Vec<_> = self.iter.by_ref().take(self.size).collect();
+        match batch.is_empty() {
+            true => None,
+            false => Some(batch),
+        }
+    }
+}
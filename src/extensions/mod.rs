@@ -1,7 +1,18 @@
+#[cfg(any(feature = "chrono", feature = "time"))]
+mod datetime;
 mod debug;
 mod display;
+mod iter;
+mod number;
+#[cfg(feature = "serde")]
 mod serialize;
+mod text;
 
+#[cfg(any(feature = "chrono", feature = "time"))]
+pub use datetime::DateTimeExt;
 pub use debug::DebugExt;
 pub use display::{DisplayExt, DisplayTemplate};
+pub use iter::IterExt;
+pub use number::NumberExt;
+#[cfg(feature = "serde")]
 pub use serialize::SerializeExt;
@@ -0,0 +1,302 @@
+use std::fmt::{self, Display, Write};
+
+/// An extension for the primitive integer and float types
+pub trait NumberExt: Number {
+    /// Create a struct that implements [`Display`] which writes the value with
+    /// `sep` between every group of three digits like `1,234,567`
+    fn grouped<S: Display>(&self, sep: S) -> Grouped<&Self, S> {
+        Grouped { value: self, sep }
+    }
+
+    /// Write the value with exactly `precision` digits after the decimal point
+    fn fixed(&self, precision: usize) -> Fixed<'_, Self> {
+        Fixed {
+            value: self,
+            precision,
+        }
+    }
+
+    /// Write the value as a percentage where `1.0` is `100%`, rounded to two decimal places
+    fn percent(&self) -> Percent<'_, Self> {
+        Percent(self)
+    }
+
+    /// Write a number of bytes in binary units like `1.2 MiB`
+    fn filesize(&self) -> FileSize<'_, Self> {
+        FileSize(self)
+    }
+
+    /// Write the value with its english ordinal suffix like `1st`, `2nd` or `11th`
+    fn ordinal(&self) -> Ordinal<'_, Self>
+    where
+        Self: Integer,
+    {
+        Ordinal(self)
+    }
+
+    /// Write the value as an amount of money like `$1,234.50` or `-€3.00`
+    ///
+    /// The amount always has two decimal places and commas between groups of digits,
+    /// use [`fixed`](NumberExt::fixed) followed by `.grouped(sep)` for other conventions.
+    fn currency<'a>(&'a self, symbol: &'a str) -> Currency<'a, Self> {
+        Currency {
+            value: self,
+            symbol,
+        }
+    }
+}
+
+impl<T: Number> NumberExt for T {}
+
+/// A primitive number type
+pub trait Number: Display {
+    fn to_f64(&self) -> f64;
+
+    // whether an integer is negative and its magnitude, `None` for floats
+    fn to_sign_magnitude(&self) -> Option<(bool, u128)>;
+
+    // write the value with `precision` digits after the decimal point
+    fn fmt_fixed(&self, precision: usize, f: &mut fmt::Formatter<'_>) -> fmt::Result;
+}
+
+/// A primitive integer type
+pub trait Integer: Number {
+    fn unsigned_abs(&self) -> u128;
+}
+
+macro_rules! impl_integer {
+    ($($ty:ty),*) => {
+        $(
+        impl Number for $ty {
+            fn to_f64(&self) -> f64 {
+                *self as f64
+            }
+
+            fn to_sign_magnitude(&self) -> Option<(bool, u128)> {
+                let negative = i128::try_from(*self).is_ok_and(|v| v < 0);
+                Some((negative, Integer::unsigned_abs(self)))
+            }
+
+            fn fmt_fixed(&self, precision: usize, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{self}")?;
+                if precision > 0 {
+                    write!(f, ".{:0<1$}", "", precision)?;
+                }
+                Ok(())
+            }
+        }
+
+        impl Integer for $ty {
+            fn unsigned_abs(&self) -> u128 {
+                // only u128 values can fail to convert and they are already unsigned
+                i128::try_from(*self)
+                    .map(i128::unsigned_abs)
+                    .unwrap_or(*self as u128)
+            }
+        }
+        )*
+    };
+}
+
+macro_rules! impl_float {
+    ($($ty:ty),*) => {
+        $(
+        impl Number for $ty {
+            fn to_f64(&self) -> f64 {
+                *self as f64
+            }
+
+            fn to_sign_magnitude(&self) -> Option<(bool, u128)> {
+                None
+            }
+
+            fn fmt_fixed(&self, precision: usize, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{self:.precision$}")
+            }
+        }
+        )*
+    };
+}
+
+impl_integer!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+impl_float!(f32, f64);
+
+impl<T: Number + ?Sized> Number for &T {
+    fn to_f64(&self) -> f64 {
+        (**self).to_f64()
+    }
+
+    fn to_sign_magnitude(&self) -> Option<(bool, u128)> {
+        (**self).to_sign_magnitude()
+    }
+
+    fn fmt_fixed(&self, precision: usize, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt_fixed(precision, f)
+    }
+}
+
+impl<T: Integer + ?Sized> Integer for &T {
+    fn unsigned_abs(&self) -> u128 {
+        (**self).unsigned_abs()
+    }
+}
+
+pub struct Grouped<V, S> {
+    value: V,
+    sep: S,
+}
+
+impl<V: Display, S: Display> Display for Grouped<V, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // count the integer digits first so the separators can be placed while writing
+        let mut counter = Digits::default();
+        write!(counter, "{}", self.value)?;
+
+        let mut writer = GroupWriter {
+            f,
+            sep: &self.sep,
+            remaining: counter.count,
+        };
+        write!(writer, "{}", self.value)
+    }
+}
+
+// counts the digits before the decimal point
+#[derive(Default)]
+struct Digits {
+    count: usize,
+    done: bool,
+}
+
+impl Write for Digits {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            match c {
+                _ if self.done => break,
+                '0'..='9' => self.count += 1,
+                '-' | '+' if self.count == 0 => {}
+                _ => self.done = true,
+            }
+        }
+        Ok(())
+    }
+}
+
+struct GroupWriter<'a, 'b, S> {
+    f: &'a mut fmt::Formatter<'b>,
+    sep: &'a S,
+    remaining: usize,
+}
+
+impl<S: Display> Write for GroupWriter<'_, '_, S> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            self.f.write_char(c)?;
+            if self.remaining > 0 && c.is_ascii_digit() {
+                self.remaining -= 1;
+                // a group ends when no digits of the next partial group are left
+                let partial = self.remaining % 3;
+                if self.remaining > 0 && partial == 0 {
+                    self.sep.fmt(self.f)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct Fixed<'a, T: ?Sized> {
+    value: &'a T,
+    precision: usize,
+}
+
+impl<'a, T: Number + ?Sized> Fixed<'a, T> {
+    /// Write the fixed value with `sep` between every group of three digits
+    pub fn grouped<S: Display>(self, sep: S) -> Grouped<Self, S> {
+        Grouped { value: self, sep }
+    }
+}
+
+impl<T: Number + ?Sized> Display for Fixed<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt_fixed(self.precision, f)
+    }
+}
+
+pub struct Percent<'a, T: ?Sized>(&'a T);
+
+impl<T: Number + ?Sized> Display for Percent<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // integers are written exactly instead of going through a float
+        match self.0.to_sign_magnitude() {
+            Some((_, 0)) => return f.write_str("0%"),
+            Some((negative, n)) => return write!(f, "{}{n}00%", if negative { "-" } else { "" }),
+            None => {}
+        }
+        // floats display as the shortest exact representation so this drops trailing zeros
+        let percent = (self.0.to_f64() * 10000.0).round() / 100.0;
+        write!(f, "{percent}%")
+    }
+}
+
+pub struct FileSize<'a, T: ?Sized>(&'a T);
+
+impl<T: Number + ?Sized> Display for FileSize<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const UNITS: [&str; 6] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+        let bytes = self.0.to_f64();
+        if bytes.abs() < 1024.0 {
+            return write!(f, "{bytes} B");
+        }
+        let mut size = bytes / 1024.0;
+        let mut unit = UNITS[0];
+        for next in &UNITS[1..] {
+            if size.abs() < 1024.0 {
+                break;
+            }
+            size /= 1024.0;
+            unit = next;
+        }
+        write!(f, "{size:.1} {unit}")
+    }
+}
+
+pub struct Ordinal<'a, T: ?Sized>(&'a T);
+
+impl<T: Integer + ?Sized> Display for Ordinal<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let n = self.0.unsigned_abs();
+        let suffix = match (n % 10, n % 100) {
+            (_, 11..=13) => "th",
+            (1, _) => "st",
+            (2, _) => "nd",
+            (3, _) => "rd",
+            _ => "th",
+        };
+        write!(f, "{}{suffix}", self.0)
+    }
+}
+
+pub struct Currency<'a, T: ?Sized> {
+    value: &'a T,
+    symbol: &'a str,
+}
+
+impl<T: Number + ?Sized> Display for Currency<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some((negative, n)) = self.value.to_sign_magnitude() {
+            if negative {
+                f.write_char('-')?;
+            }
+            f.write_str(self.symbol)?;
+            return write!(f, "{}", n.fixed(2).grouped(","));
+        }
+        let value = self.value.to_f64();
+        // amounts which round to zero are written without a sign
+        if value < 0.0 && (value * 100.0).round() != 0.0 {
+            f.write_char('-')?;
+        }
+        f.write_str(self.symbol)?;
+        write!(f, "{}", value.abs().fixed(2).grouped(","))
+    }
+}
@@ -1,5 +1,9 @@
+use std::borrow::Cow;
+use std::fmt;
 use std::io;
 
+use crate::escaping::{Html, SafeIn, Script};
+
 /// An extension to types that implement [`Serialize`](serde::Serialize)
 pub trait SerializeExt {
     /// Create a struct that implements [`Display`] that will
@@ -9,6 +13,35 @@ pub trait SerializeExt {
     /// [`Display`]: std::fmt::Display
     /// [`Serialize`]: serde::Serialize
     fn json(&self) -> Jsonify<'_, Self>;
+
+    /// Like [`json`](SerializeExt::json) but safe to write inside of a `<script>` element
+    ///
+    /// `<`, `>`, `&`, U+2028, and U+2029 are written as unicode escapes, they can only
+    /// appear inside of json strings so the output is still valid json and javascript.
+    /// The result is written unchanged as code in a `<script>`, anywhere else it is escaped.
+    fn json_script(&self) -> JsonScript<'_, Self>;
+
+    /// Like [`json_script`](SerializeExt::json_script) but also safe to write inside
+    /// of a quoted html attribute, quotes are written as html entities
+    ///
+    /// The result is written unchanged in html text and quoted attributes, anywhere else
+    /// it is escaped.
+    fn json_attr(&self) -> JsonAttr<'_, Self>;
+
+    /// Like [`json`](SerializeExt::json) but indented over multiple lines
+    fn json_pretty(&self) -> JsonPretty<'_, Self>;
+
+    /// Create a struct that implements [`Display`](std::fmt::Display) that will
+    /// create a yaml document from the object, this requires the `yaml` feature
+    #[cfg(feature = "yaml")]
+    fn yaml(&self) -> Yaml<'_, Self>;
+
+    /// Create a struct that implements [`Display`](std::fmt::Display) that will
+    /// create a toml document from the object, this requires the `toml` feature
+    ///
+    /// Only types which serialize to a table like structs and maps can be written as toml.
+    #[cfg(feature = "toml")]
+    fn toml(&self) -> Toml<'_, Self>;
 }
 
 impl<T> SerializeExt for T
@@ -18,6 +51,28 @@ where
     fn json(&self) -> Jsonify<'_, Self> {
         Jsonify(self)
     }
+
+    fn json_script(&self) -> JsonScript<'_, Self> {
+        JsonScript(self)
+    }
+
+    fn json_attr(&self) -> JsonAttr<'_, Self> {
+        JsonAttr(self)
+    }
+
+    fn json_pretty(&self) -> JsonPretty<'_, Self> {
+        JsonPretty(self)
+    }
+
+    #[cfg(feature = "yaml")]
+    fn yaml(&self) -> Yaml<'_, Self> {
+        Yaml(self)
+    }
+
+    #[cfg(feature = "toml")]
+    fn toml(&self) -> Toml<'_, Self> {
+        Toml(self)
+    }
 }
 
 pub struct Jsonify<'a, T: ?Sized>(&'a T);
@@ -27,22 +82,180 @@ where
     T: ?Sized + serde::Serialize,
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        serde_json::to_writer(FmtWriter(f), self.0).map_err(|_| std::fmt::Error)
+        let mut writer = FmtWriter::new(f);
+        serde_json::to_writer(&mut writer, self.0).map_err(|_| fmt::Error)?;
+        writer.finish()
+    }
+}
+
+pub struct JsonPretty<'a, T: ?Sized>(&'a T);
+
+impl<T> std::fmt::Display for JsonPretty<'_, T>
+where
+    T: ?Sized + serde::Serialize,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut writer = FmtWriter::new(f);
+        serde_json::to_writer_pretty(&mut writer, self.0).map_err(|_| fmt::Error)?;
+        writer.finish()
+    }
+}
+
+#[cfg(feature = "yaml")]
+pub struct Yaml<'a, T: ?Sized>(&'a T);
+
+#[cfg(feature = "yaml")]
+impl<T> std::fmt::Display for Yaml<'_, T>
+where
+    T: ?Sized + serde::Serialize,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut writer = FmtWriter::new(f);
+        serde_yaml_ng::to_writer(&mut writer, self.0).map_err(|_| fmt::Error)?;
+        writer.finish()
     }
 }
 
-struct FmtWriter<'a, 'b>(&'a mut std::fmt::Formatter<'b>);
+#[cfg(feature = "toml")]
+pub struct Toml<'a, T: ?Sized>(&'a T);
 
-impl<'a, 'b> std::io::Write for FmtWriter<'a, 'b> {
-    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        let s = String::from_utf8_lossy(buf);
-        self.0
-            .write_str(&s)
+#[cfg(feature = "toml")]
+impl<T> std::fmt::Display for Toml<'_, T>
+where
+    T: ?Sized + serde::Serialize,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // the toml serializer only writes into strings
+        let toml = toml::to_string(self.0).map_err(|_| fmt::Error)?;
+        f.write_str(&toml)
+    }
+}
+
+pub struct JsonScript<'a, T: ?Sized>(&'a T);
+
+impl<T> std::fmt::Display for JsonScript<'_, T>
+where
+    T: ?Sized + serde::Serialize,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut writer = FmtWriter::new(EmbedJson { f, attr: false });
+        serde_json::to_writer(&mut writer, self.0).map_err(|_| fmt::Error)?;
+        writer.finish()
+    }
+}
+
+impl<T: ?Sized + serde::Serialize> SafeIn<Script> for JsonScript<'_, T> {}
+
+pub struct JsonAttr<'a, T: ?Sized>(&'a T);
+
+impl<T> std::fmt::Display for JsonAttr<'_, T>
+where
+    T: ?Sized + serde::Serialize,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut writer = FmtWriter::new(EmbedJson { f, attr: true });
+        serde_json::to_writer(&mut writer, self.0).map_err(|_| fmt::Error)?;
+        writer.finish()
+    }
+}
+
+// the html escaper is used in text and quoted attributes, unquoted ones use `HtmlAttr`
+impl<T: ?Sized + serde::Serialize> SafeIn<Html> for JsonAttr<'_, T> {}
+
+// escapes the characters that could end a script or attribute from serialized json
+struct EmbedJson<'a, 'b> {
+    f: &'a mut std::fmt::Formatter<'b>,
+    attr: bool,
+}
+
+impl std::fmt::Write for EmbedJson<'_, '_> {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        for c in s.chars() {
+            match c {
+                '<' | '>' | '&' | '\u{2028}' | '\u{2029}' => {
+                    write!(self.f, "\\u{:04x}", c as u32)?
+                }
+                '"' if self.attr => self.f.write_str("&quot;")?,
+                '\'' if self.attr => self.f.write_str("&#39;")?,
+                c => self.f.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Adapts a [`fmt::Write`] for serializers which write to an [`io::Write`]
+///
+/// Serializers may split a multi-byte character between writes, the start of
+/// it is kept until the rest arrives so that only whole characters are written.
+struct FmtWriter<W> {
+    inner: W,
+    pending: Vec<u8>,
+}
+
+impl<W: fmt::Write> FmtWriter<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            pending: Vec::new(),
+        }
+    }
+
+    // fails if the output ended in the middle of a character
+    fn finish(self) -> fmt::Result {
+        match self.pending.is_empty() {
+            true => Ok(()),
+            false => Err(fmt::Error),
+        }
+    }
+}
+
+impl<W: fmt::Write> io::Write for FmtWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let bytes = match self.pending.is_empty() {
+            true => Cow::Borrowed(buf),
+            false => {
+                let mut bytes = std::mem::take(&mut self.pending);
+                bytes.extend_from_slice(buf);
+                Cow::Owned(bytes)
+            }
+        };
+        let valid = match std::str::from_utf8(&bytes) {
+            Ok(s) => s,
+            // the end is an incomplete character that may be finished by the next write
+            Err(e) if e.error_len().is_none() => {
+                self.pending.extend_from_slice(&bytes[e.valid_up_to()..]);
+                std::str::from_utf8(&bytes[..e.valid_up_to()]).unwrap()
+            }
+            Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+        };
+        self.inner
+            .write_str(valid)
             .map(|_| buf.len())
-            .map_err(|_| io::Error::new(io::ErrorKind::Other, "unable to format"))
+            .map_err(io::Error::other)
     }
 
-    fn flush(&mut self) -> std::io::Result<()> {
+    fn flush(&mut self) -> io::Result<()> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use super::FmtWriter;
+
+    #[test]
+    fn split_characters() {
+        let bytes = "é✓".as_bytes();
+        let mut out = String::new();
+        let mut writer = FmtWriter::new(&mut out);
+        for b in bytes {
+            writer.write_all(std::slice::from_ref(b)).unwrap();
+        }
+        writer.finish().unwrap();
+
+        assert_eq!(out, "é✓");
+    }
+}
@@ -0,0 +1,490 @@
+use std::fmt::{self, Display, Formatter, Write};
+
+use crate::escaping::{is_unreserved, write_percent, Html, SafeIn};
+
+/// A transformation applied to text while a value is being formatted
+///
+/// The output of the value arrives in pieces, so filters which need to see more than one
+/// character at a time keep what they need in their own state until the next piece.
+pub trait Filter: Clone {
+    fn write(&mut self, f: &mut Formatter<'_>, s: &str) -> fmt::Result;
+
+    /// Called once the whole value has been written
+    fn finish(&mut self, _f: &mut Formatter<'_>) -> fmt::Result {
+        Ok(())
+    }
+}
+
+/// A value which is displayed through a [`Filter`]
+pub struct Filtered<'a, T: ?Sized, F> {
+    value: &'a T,
+    filter: F,
+}
+
+impl<'a, T: ?Sized, F> Filtered<'a, T, F> {
+    pub(crate) fn new(value: &'a T, filter: F) -> Self {
+        Self { value, filter }
+    }
+}
+
+impl<T, F> Display for Filtered<'_, T, F>
+where
+    T: Display + ?Sized,
+    F: Filter,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut writer = FilterWriter {
+            f,
+            filter: self.filter.clone(),
+        };
+        write!(writer, "{}", self.value)?;
+        writer.filter.finish(writer.f)
+    }
+}
+
+// the output is escaped html, unquoted attributes and other escapers still escape it
+impl<T: Display + ?Sized> SafeIn<Html> for Filtered<'_, T, Linebreaks> {}
+
+struct FilterWriter<'a, 'b, F> {
+    f: &'a mut Formatter<'b>,
+    filter: F,
+}
+
+impl<F: Filter> Write for FilterWriter<'_, '_, F> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.filter.write(self.f, s)
+    }
+}
+
+#[derive(Clone)]
+pub struct Lowercase;
+
+impl Filter for Lowercase {
+    fn write(&mut self, f: &mut Formatter<'_>, s: &str) -> fmt::Result {
+        s.chars().flat_map(char::to_lowercase).try_for_each(|c| f.write_char(c))
+    }
+}
+
+#[derive(Clone)]
+pub struct Uppercase;
+
+impl Filter for Uppercase {
+    fn write(&mut self, f: &mut Formatter<'_>, s: &str) -> fmt::Result {
+        s.chars().flat_map(char::to_uppercase).try_for_each(|c| f.write_char(c))
+    }
+}
+
+#[derive(Clone)]
+pub struct Truncate {
+    remaining: usize,
+    cut: bool,
+}
+
+impl Truncate {
+    pub(crate) fn new(len: usize) -> Self {
+        Self {
+            remaining: len,
+            cut: false,
+        }
+    }
+}
+
+impl Filter for Truncate {
+    fn write(&mut self, f: &mut Formatter<'_>, s: &str) -> fmt::Result {
+        if self.cut {
+            return Ok(());
+        }
+        match s.char_indices().nth(self.remaining) {
+            Some((end, _)) => {
+                self.cut = true;
+                self.remaining = 0;
+                f.write_str(&s[..end])
+            }
+            None => {
+                self.remaining -= s.chars().count();
+                f.write_str(s)
+            }
+        }
+    }
+
+    fn finish(&mut self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.cut {
+            true => f.write_str("..."),
+            false => Ok(()),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct WordWrap {
+    width: usize,
+    line: usize,
+    word: String,
+}
+
+impl WordWrap {
+    pub(crate) fn new(width: usize) -> Self {
+        Self {
+            width,
+            line: 0,
+            word: String::new(),
+        }
+    }
+
+    // write the current word on this line if it fits or on a new line if it does not
+    fn flush(&mut self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.word.is_empty() {
+            return Ok(());
+        }
+        let len = self.word.chars().count();
+        if self.line > 0 {
+            if self.line + 1 + len > self.width {
+                f.write_char('\n')?;
+                self.line = 0;
+            } else {
+                f.write_char(' ')?;
+                self.line += 1;
+            }
+        }
+        f.write_str(&self.word)?;
+        self.line += len;
+        self.word.clear();
+        Ok(())
+    }
+}
+
+impl Filter for WordWrap {
+    fn write(&mut self, f: &mut Formatter<'_>, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            match c {
+                '\n' => {
+                    self.flush(f)?;
+                    f.write_char('\n')?;
+                    self.line = 0;
+                }
+                c if c.is_whitespace() => self.flush(f)?,
+                c => self.word.push(c),
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.flush(f)
+    }
+}
+
+#[derive(Clone)]
+pub struct Indent {
+    width: usize,
+    line_start: bool,
+}
+
+impl Indent {
+    pub(crate) fn new(width: usize) -> Self {
+        Self {
+            width,
+            line_start: false,
+        }
+    }
+}
+
+impl Filter for Indent {
+    fn write(&mut self, f: &mut Formatter<'_>, s: &str) -> fmt::Result {
+        for line in s.split_inclusive('\n') {
+            // blank lines are left empty
+            if self.line_start && line != "\n" {
+                write!(f, "{:1$}", "", self.width)?;
+            }
+            f.write_str(line)?;
+            self.line_start = line.ends_with('\n');
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct Title {
+    in_word: bool,
+}
+
+impl Title {
+    pub(crate) fn new() -> Self {
+        Self { in_word: false }
+    }
+}
+
+impl Filter for Title {
+    fn write(&mut self, f: &mut Formatter<'_>, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            match (c.is_alphanumeric(), self.in_word) {
+                (true, true) => c.to_lowercase().try_for_each(|c| f.write_char(c))?,
+                (true, false) => c.to_uppercase().try_for_each(|c| f.write_char(c))?,
+                (false, _) => f.write_char(c)?,
+            }
+            // an apostrophe does not start a new word in words like "don't"
+            self.in_word = c.is_alphanumeric() || (c == '\'' && self.in_word);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct Capitalize {
+    first: bool,
+}
+
+impl Capitalize {
+    pub(crate) fn new() -> Self {
+        Self { first: true }
+    }
+}
+
+impl Filter for Capitalize {
+    fn write(&mut self, f: &mut Formatter<'_>, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            match std::mem::take(&mut self.first) {
+                true => c.to_uppercase().try_for_each(|c| f.write_char(c))?,
+                false => c.to_lowercase().try_for_each(|c| f.write_char(c))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct Trim {
+    started: bool,
+    whitespace: String,
+}
+
+impl Trim {
+    pub(crate) fn new() -> Self {
+        Self {
+            started: false,
+            whitespace: String::new(),
+        }
+    }
+}
+
+impl Filter for Trim {
+    fn write(&mut self, f: &mut Formatter<'_>, s: &str) -> fmt::Result {
+        let s = match self.started {
+            true => s,
+            false => s.trim_start(),
+        };
+        let body = s.trim_end();
+        // trailing whitespace is only written once something follows it
+        if !body.is_empty() {
+            f.write_str(&self.whitespace)?;
+            f.write_str(body)?;
+            self.whitespace.clear();
+            self.started = true;
+        }
+        self.whitespace.push_str(&s[body.len()..]);
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct Replace<'a> {
+    from: &'a str,
+    to: &'a str,
+    pending: String,
+}
+
+impl<'a> Replace<'a> {
+    pub(crate) fn new(from: &'a str, to: &'a str) -> Self {
+        Self {
+            from,
+            to,
+            pending: String::new(),
+        }
+    }
+}
+
+impl Filter for Replace<'_> {
+    fn write(&mut self, f: &mut Formatter<'_>, s: &str) -> fmt::Result {
+        if self.from.is_empty() {
+            return f.write_str(s);
+        }
+        self.pending.push_str(s);
+        let pending = self.pending.as_str();
+
+        let mut start = 0;
+        while let Some(i) = pending[start..].find(self.from) {
+            f.write_str(&pending[start..start + i])?;
+            f.write_str(self.to)?;
+            start += i + self.from.len();
+        }
+        // hold on to the end if it could be the start of a match in the next piece
+        let keep = (pending.len().saturating_sub(self.from.len() - 1).max(start)..pending.len())
+            .find(|i| pending.is_char_boundary(*i) && self.from.starts_with(&pending[*i..]))
+            .unwrap_or(pending.len());
+        f.write_str(&pending[start..keep])?;
+        self.pending.drain(..keep);
+        Ok(())
+    }
+
+    fn finish(&mut self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.pending)
+    }
+}
+
+#[derive(Clone)]
+pub struct Linebreaks {
+    started: bool,
+    newlines: usize,
+}
+
+impl Linebreaks {
+    pub(crate) fn new() -> Self {
+        Self {
+            started: false,
+            newlines: 0,
+        }
+    }
+}
+
+impl Filter for Linebreaks {
+    fn write(&mut self, f: &mut Formatter<'_>, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            match c {
+                '\r' => {}
+                '\n' => self.newlines += 1,
+                c => {
+                    match (self.started, self.newlines) {
+                        (false, _) => f.write_str("<p>")?,
+                        (true, 0) => {}
+                        (true, 1) => f.write_str("<br>")?,
+                        (true, _) => f.write_str("</p><p>")?,
+                    }
+                    self.started = true;
+                    self.newlines = 0;
+                    match c {
+                        '&' => f.write_str("&amp;")?,
+                        '<' => f.write_str("&lt;")?,
+                        '>' => f.write_str("&gt;")?,
+                        '"' => f.write_str("&quot;")?,
+                        '\'' => f.write_str("&#x27;")?,
+                        '/' => f.write_str("&#x2F;")?,
+                        c => f.write_char(c)?,
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.started {
+            true => f.write_str("</p>"),
+            false => Ok(()),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct StripTags {
+    state: TagState,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum TagState {
+    Text,
+    // a `<` which may or may not start a tag
+    Open,
+    Tag,
+}
+
+impl StripTags {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: TagState::Text,
+        }
+    }
+}
+
+impl Filter for StripTags {
+    fn write(&mut self, f: &mut Formatter<'_>, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            self.state = match (self.state, c) {
+                (TagState::Tag, '>') => TagState::Text,
+                (TagState::Tag, _) => TagState::Tag,
+                (TagState::Open, c) if c.is_ascii_alphabetic() || matches!(c, '/' | '!' | '?') => {
+                    TagState::Tag
+                }
+                (state, '<') => {
+                    if state == TagState::Open {
+                        f.write_char('<')?;
+                    }
+                    TagState::Open
+                }
+                (state, c) => {
+                    if state == TagState::Open {
+                        f.write_char('<')?;
+                    }
+                    f.write_char(c)?;
+                    TagState::Text
+                }
+            };
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.state {
+            TagState::Open => f.write_char('<'),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Slugify {
+    started: bool,
+    dash: bool,
+}
+
+impl Slugify {
+    pub(crate) fn new() -> Self {
+        Self {
+            started: false,
+            dash: false,
+        }
+    }
+}
+
+impl Filter for Slugify {
+    fn write(&mut self, f: &mut Formatter<'_>, s: &str) -> fmt::Result {
+        for c in s.chars().flat_map(char::to_lowercase) {
+            if !c.is_alphanumeric() {
+                self.dash = true;
+                continue;
+            }
+            // separators are only written between words
+            if self.dash && self.started {
+                f.write_char('-')?;
+            }
+            f.write_char(c)?;
+            self.started = true;
+            self.dash = false;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct UrlEncode;
+
+impl Filter for UrlEncode {
+    fn write(&mut self, f: &mut Formatter<'_>, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            match is_unreserved(c) {
+                true => f.write_char(c)?,
+                false => write_percent(c, f)?,
+            }
+        }
+        Ok(())
+    }
+}
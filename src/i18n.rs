@@ -0,0 +1,347 @@
+//! Translate templates with [Fluent](https://projectfluent.org)
+//!
+//! Enabling the `i18n` feature adds the `{% t "message-id" { name: user.name } %}` expression
+//! to templates. Messages are read from `.ftl` files in `i18n_dir/<locale>/`, which defaults
+//! to `locales` next to `Cargo.toml`. At compile time every message id and argument is checked
+//! against the `default_locale`, at runtime the messages of the current locale are used and
+//! anything missing from it falls back to the default locale.
+//!
+//! The locale of a template is the one passed to the generated `render_locale` or `fmt_locale`
+//! methods, then a field marked with `#[stilts(locale)]`, and otherwise the default locale.
+//! The locale is part of the template or the render call, so it is the same for streamed and
+//! async rendering which may move between threads. Macros use the locale of the template which
+//! calls them.
+//!
+//! Every crate with templates which use translations calls [`translations!`] once at its root,
+//! which embeds every locale in the directory into the crate, so a deployed binary does not need
+//! the `.ftl` files and changing them recompiles the crate. The templates of a crate share its
+//! translations, which are loaded the first time one of their messages is rendered, so templates
+//! from different crates each use their own. To load translations at runtime instead,
+//! for example to update them without a rebuild, set the `STILTS_I18N_DIR` environment variable
+//! to a directory laid out like `i18n_dir`, or call [`init`] at startup. Messages fail to render
+//! if their translations could not be loaded, [`Translations::load`] returns the error.
+//!
+//! ```toml
+//! [package.metadata.stilts]
+//! i18n_dir = "$CARGO_MANIFEST_DIR/locales"
+//! default_locale = "en"
+//! ```
+//!
+//! ```ignore
+//! // in lib.rs or main.rs
+//! stilts::i18n::translations!();
+//! ```
+
+use std::borrow::Cow;
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource, FluentValue};
+use unic_langid::LanguageIdentifier;
+
+/// Embed the translations of the crate it is called in for its templates to use
+///
+/// This has to be called once at the root of every crate with templates which use
+/// translations, they fail to compile without it.
+pub use stilts_macros::translations;
+
+static INITIALIZED: OnceLock<Translations> = OnceLock::new();
+
+/// The environment variable with a directory to load translations from at runtime
+pub const DIR_VAR: &str = "STILTS_I18N_DIR";
+
+/// Use `translations` for every template instead of the translations they were compiled with
+///
+/// This is meant to be called once at startup by the application, it fails and gives the
+/// translations back if it was called before.
+pub fn init(translations: Translations) -> Result<(), Translations> {
+    INITIALIZED.set(translations)
+}
+
+/// Errors from loading translations
+#[derive(Debug)]
+pub enum Error {
+    /// A locale directory or file could not be read
+    Io(io::Error),
+    /// A locale is not a valid language identifier
+    Locale(String),
+    /// Fluent source could not be parsed or contains a message twice
+    Fluent {
+        /// The locale the source was added to
+        locale: String,
+        /// What went wrong
+        message: String,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "unable to read translations: {err}"),
+            Self::Locale(locale) => write!(f, "invalid locale `{locale}`"),
+            Self::Fluent { locale, message } => {
+                write!(f, "invalid translations for `{locale}`: {message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+fn parse_locale(locale: &str) -> Result<LanguageIdentifier, Error> {
+    locale.parse().map_err(|_| Error::Locale(locale.to_string()))
+}
+
+/// The Fluent messages of every locale
+pub struct Translations {
+    bundles: Vec<(LanguageIdentifier, FluentBundle<FluentResource>)>,
+    default: LanguageIdentifier,
+}
+
+impl Translations {
+    /// Create translations without any messages which fall back to `default_locale`
+    pub fn new(default_locale: &str) -> Result<Self, Error> {
+        Ok(Self {
+            bundles: Vec::new(),
+            default: parse_locale(default_locale)?,
+        })
+    }
+
+    /// Load every `.ftl` file in the subdirectories of `dir`, each subdirectory is named
+    /// after the locale of the files in it like `locales/en/main.ftl`
+    pub fn load(dir: impl AsRef<Path>, default_locale: &str) -> Result<Self, Error> {
+        let mut translations = Self::new(default_locale)?;
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if !path.is_dir() {
+                continue;
+            }
+            let Some(locale) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            let mut files = std::fs::read_dir(&path)?
+                .map(|entry| entry.map(|e| e.path()))
+                .collect::<io::Result<Vec<_>>>()?;
+            files.retain(|file| file.extension().is_some_and(|ext| ext == "ftl"));
+            files.sort();
+            for file in files {
+                translations.add_source(locale, std::fs::read_to_string(file)?)?;
+            }
+        }
+        Ok(translations)
+    }
+
+    /// Add the messages in some Fluent source to a locale
+    pub fn add_source(&mut self, locale: &str, source: impl Into<String>) -> Result<(), Error> {
+        let fluent_err = |message: String| Error::Fluent {
+            locale: locale.to_string(),
+            message,
+        };
+        let resource = FluentResource::try_new(source.into()).map_err(|(_, errors)| {
+            fluent_err(errors.iter().map(|e| e.kind.to_string()).collect::<Vec<_>>().join(", "))
+        })?;
+
+        let id = parse_locale(locale)?;
+        let index = match self.bundles.iter().position(|(l, _)| *l == id) {
+            Some(index) => index,
+            None => {
+                let mut bundle = FluentBundle::new_concurrent(vec![id.clone()]);
+                // the isolation marks would end up in the html around every argument
+                bundle.set_use_isolating(false);
+                self.bundles.push((id, bundle));
+                self.bundles.len() - 1
+            }
+        };
+        self.bundles[index].1.add_resource(resource).map_err(|errors| {
+            fluent_err(errors.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "))
+        })
+    }
+
+    // the bundle of a locale which has a message, the exact locale is preferred over one
+    // with the same language, and the default locale is used if neither has it
+    fn bundle(&self, locale: Option<&str>, id: &str) -> Option<&FluentBundle<FluentResource>> {
+        let has = |(_, bundle): &&(_, FluentBundle<_>)| bundle.has_message(id);
+        let requested = locale.and_then(|l| l.parse::<LanguageIdentifier>().ok());
+        let exact = requested
+            .as_ref()
+            .and_then(|req| self.bundles.iter().filter(has).find(|(l, _)| l == req));
+        let language = || {
+            let req = requested.as_ref()?;
+            self.bundles
+                .iter()
+                .filter(has)
+                .find(|(l, _)| l.language == req.language)
+        };
+        let default = || self.bundles.iter().filter(has).find(|(l, _)| *l == self.default);
+        exact.or_else(language).or_else(default).map(|(_, bundle)| bundle)
+    }
+
+    /// Write a message or one of its attributes in a locale
+    ///
+    /// This fails if neither the locale nor the default locale have the message.
+    pub fn write(
+        &self,
+        w: &mut (impl fmt::Write + ?Sized),
+        locale: Option<&str>,
+        id: &str,
+        attribute: Option<&str>,
+        args: &FluentArgs,
+    ) -> fmt::Result {
+        let bundle = self.bundle(locale, id).ok_or(fmt::Error)?;
+        let message = bundle.get_message(id).ok_or(fmt::Error)?;
+        let pattern = match attribute {
+            Some(attr) => message.get_attribute(attr).ok_or(fmt::Error)?.value(),
+            None => message.value().ok_or(fmt::Error)?,
+        };
+        let mut errors = Vec::new();
+        bundle.write_pattern(&mut FmtWrite(w), pattern, Some(args), &mut errors)
+    }
+}
+
+// `write_pattern` requires a sized writer
+struct FmtWrite<'a, W: ?Sized>(&'a mut W);
+
+impl<W: fmt::Write + ?Sized> fmt::Write for FmtWrite<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.write_str(s)
+    }
+}
+
+/// Values which can be passed as arguments to a translation
+///
+/// Numbers stay numbers so Fluent can select plural forms with them.
+pub trait FluentArg {
+    /// Convert the value into a Fluent value
+    fn to_fluent(&self) -> FluentValue<'_>;
+}
+
+impl FluentArg for str {
+    fn to_fluent(&self) -> FluentValue<'_> {
+        FluentValue::from(self)
+    }
+}
+
+impl FluentArg for String {
+    fn to_fluent(&self) -> FluentValue<'_> {
+        FluentValue::from(self.as_str())
+    }
+}
+
+impl FluentArg for Cow<'_, str> {
+    fn to_fluent(&self) -> FluentValue<'_> {
+        FluentValue::from(self.as_ref())
+    }
+}
+
+impl<T: FluentArg + ?Sized> FluentArg for &T {
+    fn to_fluent(&self) -> FluentValue<'_> {
+        (**self).to_fluent()
+    }
+}
+
+macro_rules! impl_fluent_number {
+    ($($ty:ty),*) => {
+        $(
+        impl FluentArg for $ty {
+            fn to_fluent(&self) -> FluentValue<'_> {
+                FluentValue::from(*self)
+            }
+        }
+        )*
+    };
+}
+
+impl_fluent_number!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64);
+
+/// Where the translations of a crate are loaded from if [`init`] was not called, created
+/// by [`translations!`]
+#[doc(hidden)]
+pub struct Source {
+    embedded: &'static [(&'static str, &'static str)],
+    default_locale: &'static str,
+    loaded: OnceLock<Result<Translations, Error>>,
+}
+
+impl Source {
+    /// `embedded` is the Fluent source of each locale compiled into the crate
+    pub const fn new(
+        embedded: &'static [(&'static str, &'static str)],
+        default_locale: &'static str,
+    ) -> Self {
+        Self {
+            embedded,
+            default_locale,
+            loaded: OnceLock::new(),
+        }
+    }
+
+    fn load(&self) -> Result<Translations, Error> {
+        if let Some(dir) = std::env::var_os(DIR_VAR) {
+            return Translations::load(dir, self.default_locale);
+        }
+        let mut translations = Translations::new(self.default_locale)?;
+        for (locale, source) in self.embedded {
+            translations.add_source(locale, *source)?;
+        }
+        Ok(translations)
+    }
+
+    fn translations(&self) -> Result<&Translations, &Error> {
+        match INITIALIZED.get() {
+            Some(translations) => Ok(translations),
+            None => self.loaded.get_or_init(|| self.load()).as_ref(),
+        }
+    }
+}
+
+/// A translated message inside of a template
+#[doc(hidden)]
+pub struct Message<'a, const N: usize> {
+    source: &'static Source,
+    locale: Option<&'a str>,
+    id: &'static str,
+    attribute: Option<&'static str>,
+    args: [(&'static str, FluentValue<'a>); N],
+}
+
+impl<'a, const N: usize> Message<'a, N> {
+    pub fn new(
+        source: &'static Source,
+        locale: Option<&'a str>,
+        id: &'static str,
+        attribute: Option<&'static str>,
+        args: [(&'static str, FluentValue<'a>); N],
+    ) -> Self {
+        Self {
+            source,
+            locale,
+            id,
+            attribute,
+            args,
+        }
+    }
+}
+
+impl<const N: usize> fmt::Display for Message<'_, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let translations = self.source.translations().map_err(|_| fmt::Error)?;
+        let args = self.args.iter().cloned().collect::<FluentArgs>();
+        translations.write(f, self.locale, self.id, self.attribute, &args)
+    }
+}
@@ -0,0 +1,69 @@
+//! Adapters for rendering templates into byte oriented writers
+//!
+//! Templates are rendered through [`fmt::Write`], these types bridge that into
+//! [`std::io::Write`] implementations and raw byte buffers. Normally these are used
+//! through [`Template::write_to`](crate::Template::write_to) and
+//! [`Template::render_bytes`](crate::Template::render_bytes).
+//!
+//! [`bytes::BytesMut`](https://docs.rs/bytes/latest/bytes/struct.BytesMut.html) already
+//! implements [`fmt::Write`] so it can be passed to [`Template::fmt`](crate::Template::fmt) directly.
+
+use std::fmt;
+use std::io;
+
+/// Adapts an [`io::Write`] so that it can be written to using [`fmt::Write`]
+///
+/// Unlike [`write!`] on an [`io::Write`] this keeps the underlying [`io::Error`]
+/// so that it can be returned from [`IoWriter::finish`] instead of an opaque [`fmt::Error`].
+pub struct IoWriter<'a, W: ?Sized> {
+    inner: &'a mut W,
+    error: Option<io::Error>,
+}
+
+impl<'a, W> IoWriter<'a, W>
+where
+    W: io::Write + ?Sized,
+{
+    /// Wrap a writer
+    pub fn new(inner: &'a mut W) -> Self {
+        Self { inner, error: None }
+    }
+
+    /// Convert the result of formatting into this writer into an [`io::Result`]
+    ///
+    /// If the formatting failed because of the underlying writer its error is returned,
+    /// otherwise a formatting error is converted into an [`io::Error`].
+    pub fn finish(self, res: fmt::Result) -> io::Result<()> {
+        match (res, self.error) {
+            (_, Some(err)) => Err(err),
+            (Ok(()), None) => Ok(()),
+            (Err(err), None) => Err(io::Error::other(err)),
+        }
+    }
+}
+
+impl<W> fmt::Write for IoWriter<'_, W>
+where
+    W: io::Write + ?Sized,
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|err| {
+            self.error = Some(err);
+            fmt::Error
+        })
+    }
+}
+
+/// Writes directly into a byte vector
+///
+/// Appending to a [`Vec<u8>`] can not fail, so this skips the error handling
+/// that going through [`IoWriter`] would require.
+pub struct ByteWriter<'a>(pub &'a mut Vec<u8>);
+
+impl fmt::Write for ByteWriter<'_> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.extend_from_slice(s.as_bytes());
+        Ok(())
+    }
+}
@@ -42,8 +42,16 @@
 //! [package.metadata.stilts]
 //! template_dir = "$CARGO_MANIFEST_DIR/templates"
 //! trim = false
+//! contextual_escape = true
 //! writer_name = "_w"
-//! escape = { html = "::stilts::escaping::Html", "htm" = "::stilts::escaping::Html" }
+//!
+//! [package.metadata.stilts.escape]
+//! "::stilts::escaping::Html" = ["html", "htm"]
+//! "::stilts::escaping::JsString" = ["js", "mjs"]
+//! "::stilts::escaping::Json" = ["json"]
+//! "::stilts::escaping::Xml" = ["xml", "svg"]
+//! "::stilts::escaping::Latex" = ["tex"]
+//! "::stilts::escaping::Shell" = ["sh"]
 //! ```
 
 #![forbid(unsafe_code)]
@@ -51,7 +59,12 @@
 
 use std::fmt::Write;
 
-pub use extensions::{DebugExt, DisplayExt, SerializeExt};
+pub use dynamic::DynTemplate;
+pub use extensions::{DebugExt, DisplayExt, IterExt, NumberExt};
+#[cfg(feature = "serde")]
+pub use extensions::SerializeExt;
+#[cfg(any(feature = "chrono", feature = "time"))]
+pub use extensions::DateTimeExt;
 
 /// Create a stilts template
 ///
@@ -65,6 +78,21 @@ pub use extensions::{DebugExt, DisplayExt, SerializeExt};
 /// - **escape**: Override the escaper detected by file extension with a specified one
 /// - **trim**: Override the trim behavior defined in your config
 /// - **block**: Only use the contents of a specific block
+/// - **stream**: Make the web framework integrations stream the response in chunks, optionally
+///   set to the chunk size e.g. `stream = 4096`. This requires the `stream` feature and the
+///   template must be `Send + 'static`, see the `stream` module. Warp has no streamed
+///   response bodies so it is a compile error with the `warp` feature
+/// - **locales**: A list of locales like `locales = ["en", "de"]`, the variant of the template for
+///   each one next to it, like `terms.de.html` for `terms.html`, is compiled as well and must exist.
+///   This generates `render_locale` and `fmt_locale` methods which use the variant for a locale and
+///   otherwise the base template, every variant uses the same fields
+///
+/// ## Field options
+/// - **ignore**: Do not bring the field into scope within the template
+/// - **size_hint**: A function or closure taking a reference to the field and returning an
+///   estimate of how many bytes it will add to the output, see [`Template::size_hint`]
+/// - **locale**: The locale that translations in the template use unless one is passed to
+///   `render_locale`, this requires the `i18n` feature, see the `i18n` module
 ///
 /// ## Examples:
 /// Standard use case
@@ -104,6 +132,27 @@ pub use extensions::{DebugExt, DisplayExt, SerializeExt};
 ///     my_data: String,
 /// }
 /// ```
+///
+/// Rendering the german variant `terms.de.html`
+/// ```ignore
+/// #[derive(Template)]
+/// #[stilts(path = "terms.html", locales = ["de", "fr"])]
+/// struct Terms {
+///     company: String,
+/// }
+///
+/// let html = terms.render_locale("de")?;
+/// ```
+///
+/// Estimating the size of a field that makes up most of the output
+/// ```ignore
+/// #[derive(Template)]
+/// #[stilts(path = "table.html")]
+/// struct MyTable {
+///     #[stilts(size_hint = |rows: &Vec<Row>| rows.len() * 64)]
+///     rows: Vec<Row>,
+/// }
+/// ```
 pub use stilts_macros::Template;
 
 #[cfg(feature = "tide")]
@@ -111,8 +160,27 @@ mod integrations;
 #[cfg(feature = "tide")]
 pub use integrations::*;
 
+#[cfg(any(feature = "tokio", feature = "futures-io"))]
+pub mod async_io;
+mod dynamic;
 pub mod escaping;
 mod extensions;
+#[cfg(feature = "i18n")]
+pub mod i18n;
+pub mod io;
+#[cfg(feature = "stream")]
+pub mod stream;
+
+/// Pick the variant of a template for a locale, the exact locale is preferred over one
+/// with the same language so `de-AT` uses `de` when there is no `de-AT` variant
+#[doc(hidden)]
+pub fn select_locale(locale: &str, variants: &[&str]) -> Option<usize> {
+    let exact = variants.iter().position(|v| v.eq_ignore_ascii_case(locale));
+    exact.or_else(|| {
+        let language = locale.split(['-', '_']).next()?;
+        variants.iter().position(|v| v.eq_ignore_ascii_case(language))
+    })
+}
 
 /// The main template trait that is implemented by the derive macro
 pub trait Template {
@@ -122,14 +190,58 @@ pub trait Template {
     /// Required to run the render function which passes a string as the writer
     fn fmt(&self, writer: &mut (impl Write + ?Sized)) -> std::fmt::Result;
 
+    /// Returns an estimate of the length of the rendered output in bytes
+    ///
+    /// The derive macro generates a lower bound from the static content that is always
+    /// rendered, plus the estimates of any fields marked with `#[stilts(size_hint = ...)]`.
+    /// This is used to preallocate the output buffer in [`render`](Template::render).
+    fn size_hint(&self) -> usize {
+        0
+    }
+
     /// Render the template to a string
     fn render(&self) -> Result<String, std::fmt::Error> {
-        let mut out = String::new();
+        let mut out = String::with_capacity(self.size_hint());
 
         self.fmt(&mut out)?;
         Ok(out)
     }
 
+    /// Render the template to a byte vector
+    ///
+    /// Appending to a [`Vec<u8>`] can not fail, so this does not keep track of errors
+    /// like [`write_to`](Template::write_to) does. To append to an existing vector pass an
+    /// [`io::ByteWriter`] to [`fmt`](Template::fmt).
+    fn render_bytes(&self) -> Result<Vec<u8>, std::fmt::Error> {
+        let mut out = Vec::with_capacity(self.size_hint());
+
+        self.fmt(&mut io::ByteWriter(&mut out))?;
+        Ok(out)
+    }
+
+    /// Render the template into an [`io::Write`](std::io::Write) like a file or socket
+    ///
+    /// Each piece of the template is written as it is rendered, so wrapping unbuffered
+    /// writers in a [`BufWriter`](std::io::BufWriter) is recommended. If the writer fails
+    /// its error is returned unchanged.
+    fn write_to(&self, writer: &mut (impl std::io::Write + ?Sized)) -> std::io::Result<()> {
+        let mut writer = io::IoWriter::new(writer);
+
+        let res = self.fmt(&mut writer);
+        writer.finish(res)
+    }
+
+    /// Render the template on a shared thread as a stream of chunks
+    ///
+    /// See the [`stream`] module for more details
+    #[cfg(feature = "stream")]
+    fn into_stream(self, chunk_size: usize) -> stream::TemplateStream
+    where
+        Self: Sized + Send + 'static,
+    {
+        stream::TemplateStream::new(self, chunk_size)
+    }
+
     /// Create a type that implements [`Display`](std::fmt::Display) using the template [`fmt`](Template::fmt)
     fn display(&self) -> extensions::DisplayTemplate<'_, Self> {
         extensions::DisplayTemplate(self)
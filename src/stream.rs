@@ -0,0 +1,279 @@
+//! Render templates as a stream of chunks
+//!
+//! Large templates can take a while to render completely, instead of buffering the
+//! entire output a [`TemplateStream`] renders the template on a separate thread and
+//! hands the output over in chunks as they are filled. At most one chunk is buffered
+//! ahead of the consumer so memory use stays bounded.
+//!
+//! Templates are rendered on a pool of threads which is shared by every stream. A template
+//! holds on to its thread until its last chunk is taken, so a slow consumer keeps a thread
+//! waiting. The pool starts a new thread whenever every thread is busy, up to
+//! [`MAX_THREADS`], after that streams wait for a thread to be freed before they produce
+//! anything. Threads which are idle for a while stop. Dropping a stream stops its rendering
+//! and frees the thread, a stream which is dropped while it waits is never rendered.
+//!
+//! A template which fails to render or panics ends its stream with an error.
+//!
+//! The web framework integrations use this when a template is derived with
+//! `#[stilts(stream)]`, or `#[stilts(stream = 4096)]` to set the chunk size.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{Condvar, Mutex, OnceLock, PoisonError};
+use std::task::{Context, Poll};
+use std::thread;
+use std::time::Duration;
+
+use bytes::{Bytes, BytesMut};
+use futures_channel::mpsc;
+use futures_core::Stream;
+
+use crate::Template;
+
+/// The chunk size used when one is not specified
+pub const DEFAULT_CHUNK_SIZE: usize = 8 * 1024;
+
+/// The most threads rendering streams at the same time
+pub const MAX_THREADS: usize = 64;
+
+/// A [`fmt::Write`] which collects output into chunks
+///
+/// Once the buffered output reaches the chunk size it is passed to the sink,
+/// any error returned by the sink stops the rendering.
+pub struct ChunkWriter<F> {
+    buf: BytesMut,
+    chunk_size: usize,
+    sink: F,
+}
+
+impl<F> ChunkWriter<F>
+where
+    F: FnMut(Bytes) -> fmt::Result,
+{
+    /// Create a new writer which emits chunks of at least `chunk_size` bytes
+    ///
+    /// # Panics
+    /// If `chunk_size` is zero
+    pub fn new(chunk_size: usize, sink: F) -> Self {
+        assert!(
+            chunk_size > 0,
+            "the chunk size of a stream must not be zero"
+        );
+        Self {
+            buf: BytesMut::with_capacity(chunk_size),
+            chunk_size,
+            sink,
+        }
+    }
+
+    /// Pass the remaining buffered output to the sink
+    pub fn finish(mut self) -> fmt::Result {
+        if self.buf.is_empty() {
+            Ok(())
+        } else {
+            (self.sink)(self.buf.split().freeze())
+        }
+    }
+}
+
+impl<F> fmt::Write for ChunkWriter<F>
+where
+    F: FnMut(Bytes) -> fmt::Result,
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.buf.extend_from_slice(s.as_bytes());
+        if self.buf.len() >= self.chunk_size {
+            let chunk = self.buf.split().freeze();
+            self.buf.reserve(self.chunk_size);
+            (self.sink)(chunk)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// A template being rendered in chunks
+///
+/// This implements [`Stream`] for use in async code, and [`Iterator`] which blocks
+/// until the next chunk is ready. Dropping the stream stops the rendering.
+pub struct TemplateStream {
+    rx: mpsc::Receiver<Result<Bytes, fmt::Error>>,
+    // the part of a chunk which has not been read yet by `AsyncRead`
+    #[cfg(any(feature = "tokio", feature = "rocket"))]
+    unread: Bytes,
+}
+
+impl TemplateStream {
+    /// Start rendering the template on the shared threads
+    ///
+    /// # Panics
+    /// If `chunk_size` is zero
+    pub fn new<T>(template: T, chunk_size: usize) -> Self
+    where
+        T: Template + Send + 'static,
+    {
+        assert!(
+            chunk_size > 0,
+            "the chunk size of a stream must not be zero"
+        );
+        let (mut tx, rx) = mpsc::channel(0);
+        spawn(Box::new(move || {
+            // the stream may have been dropped while waiting for a thread
+            if tx.is_closed() {
+                return;
+            }
+            let mut send = |item| {
+                futures_executor::block_on(std::future::poll_fn(|cx| tx.poll_ready(cx)))
+                    .and_then(|_| tx.start_send(item))
+                    .map_err(|_| fmt::Error)
+            };
+
+            let res = panic::catch_unwind(AssertUnwindSafe(|| {
+                let mut writer = ChunkWriter::new(chunk_size, |chunk| send(Ok(chunk)));
+                template.fmt(&mut writer).and_then(|_| writer.finish())
+            }));
+            // a panic is reported like any other failure so the stream does not look complete,
+            // the receiver may also have gone away which is why rendering failed
+            if !matches!(res, Ok(Ok(()))) {
+                let _ = send(Err(fmt::Error));
+            }
+        }));
+        Self {
+            rx,
+            #[cfg(any(feature = "tokio", feature = "rocket"))]
+            unread: Bytes::new(),
+        }
+    }
+}
+
+impl Stream for TemplateStream {
+    type Item = Result<Bytes, fmt::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.rx).poll_next(cx)
+    }
+}
+
+/// Used as the response body of the gotham integration
+#[cfg(feature = "gotham")]
+impl http_body::Body for TemplateStream {
+    type Data = Bytes;
+    type Error = fmt::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<http_body::Frame<Self::Data>, Self::Error>>> {
+        self.poll_next(cx)
+            .map(|chunk| chunk.map(|chunk| chunk.map(http_body::Frame::data)))
+    }
+}
+
+/// Reading fails with an [`io::Error`](std::io::Error) if the template fails to render
+#[cfg(any(feature = "tokio", feature = "rocket"))]
+impl tokio::io::AsyncRead for TemplateStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        while self.unread.is_empty() {
+            match std::task::ready!(self.as_mut().poll_next(cx)) {
+                Some(Ok(chunk)) => self.unread = chunk,
+                Some(Err(e)) => return Poll::Ready(Err(std::io::Error::other(e))),
+                None => return Poll::Ready(Ok(())),
+            }
+        }
+        let len = buf.remaining().min(self.unread.len());
+        buf.put_slice(&self.unread.split_to(len));
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Iterator for TemplateStream {
+    type Item = Result<Bytes, fmt::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        futures_executor::block_on(std::future::poll_fn(|cx| {
+            Pin::new(&mut self.rx).poll_next(cx)
+        }))
+    }
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+// how long a thread waits for another job before it stops
+const IDLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+struct Pool {
+    queue: Mutex<Queue>,
+    ready: Condvar,
+}
+
+struct Queue {
+    jobs: VecDeque<Job>,
+    // threads waiting for a job
+    idle: usize,
+    // every thread of the pool, at most `MAX_THREADS`
+    threads: usize,
+}
+
+// run a job on an idle thread of the pool, start a new one if there are none, or queue
+// the job until a thread is free if there are too many
+fn spawn(job: Job) {
+    static POOL: OnceLock<Pool> = OnceLock::new();
+    let pool = POOL.get_or_init(|| Pool {
+        queue: Mutex::new(Queue {
+            jobs: VecDeque::new(),
+            idle: 0,
+            threads: 0,
+        }),
+        ready: Condvar::new(),
+    });
+
+    let mut queue = pool.queue.lock().unwrap_or_else(PoisonError::into_inner);
+    if queue.idle > queue.jobs.len() || queue.threads >= MAX_THREADS {
+        queue.jobs.push_back(job);
+        pool.ready.notify_one();
+        return;
+    }
+    queue.threads += 1;
+    drop(queue);
+    thread::Builder::new()
+        .name("stilts-stream".into())
+        .spawn(move || work(pool, job))
+        .expect("failed to start a stream thread");
+}
+
+// run jobs until none arrive for a while
+fn work(pool: &Pool, mut job: Job) {
+    loop {
+        job();
+
+        let mut queue = pool.queue.lock().unwrap_or_else(PoisonError::into_inner);
+        queue.idle += 1;
+        let next = loop {
+            if let Some(next) = queue.jobs.pop_front() {
+                break Some(next);
+            }
+            let (guard, wait) = pool
+                .ready
+                .wait_timeout(queue, IDLE_TIMEOUT)
+                .unwrap_or_else(PoisonError::into_inner);
+            queue = guard;
+            if wait.timed_out() && queue.jobs.is_empty() {
+                break None;
+            }
+        };
+        queue.idle -= 1;
+        match next {
+            Some(next) => job = next,
+            None => {
+                queue.threads -= 1;
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::io::ErrorKind;
+use std::process::ExitCode;
+
+use miette::Report;
+use serde::Serialize;
+use stilts_codegen::config::Config;
+use stilts_lang::types::Root;
+
+// bumped whenever the shape of the syntax tree in the output changes
+const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct Output {
+    version: u32,
+    templates: BTreeMap<String, Root<'static>>,
+}
+
+// print the syntax tree of templates as json keyed by their path in the template directory
+pub fn run(config: &Config, path: Option<&str>) -> Result<ExitCode, Box<dyn Error>> {
+    let paths = match path {
+        Some(path) => vec![path.to_string()],
+        None => crate::templates(config)?,
+    };
+
+    let mut failed = 0;
+    let mut templates = BTreeMap::new();
+    for name in paths {
+        let path = config.template_dir.join(&name);
+        let source = match std::fs::read_to_string(&path) {
+            Ok(source) => source,
+            // binary files like images can live next to templates
+            Err(e) if e.kind() == ErrorKind::InvalidData => continue,
+            Err(e) => return Err(format!("{e} while reading {path}").into()),
+        };
+        match stilts_lang::parse_template(&source, config.delimiters.clone()) {
+            Ok(root) => {
+                templates.insert(name, root.into_owned());
+            }
+            Err(e) => {
+                failed += 1;
+                eprintln!("{name}\n{:?}", Report::new(e.into_owned()));
+            }
+        }
+    }
+
+    let out = Output {
+        version: SCHEMA_VERSION,
+        templates,
+    };
+    println!("{}", serde_json::to_string_pretty(&out)?);
+    Ok(match failed {
+        0 => ExitCode::SUCCESS,
+        _ => ExitCode::FAILURE,
+    })
+}
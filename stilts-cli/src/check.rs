@@ -0,0 +1,38 @@
+use std::error::Error;
+use std::io::ErrorKind;
+use std::process::ExitCode;
+
+use miette::Report;
+use stilts_codegen::config::Config;
+
+// parse every template reporting all of the errors instead of stopping at the first
+pub fn run(config: &Config) -> Result<ExitCode, Box<dyn Error>> {
+    let mut checked = 0;
+    let mut failed = 0;
+    for name in crate::templates(config)? {
+        let path = config.template_dir.join(&name);
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            // binary files like images can live next to templates
+            Err(e) if e.kind() == ErrorKind::InvalidData => continue,
+            Err(e) => return Err(format!("{e} while reading {path}").into()),
+        };
+        checked += 1;
+
+        let (_, errors) =
+            stilts_lang::parse_template_recovering(&content, config.delimiters.clone());
+        if !errors.is_empty() {
+            failed += 1;
+            eprintln!("{name}");
+        }
+        for e in errors {
+            eprintln!("{:?}", Report::new(e.into_owned()));
+        }
+    }
+
+    println!("checked {checked} templates, {failed} with errors");
+    Ok(match failed {
+        0 => ExitCode::SUCCESS,
+        _ => ExitCode::FAILURE,
+    })
+}
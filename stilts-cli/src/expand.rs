@@ -0,0 +1,33 @@
+use std::error::Error;
+use std::process::ExitCode;
+
+use quote::quote;
+use stilts_codegen::config::Config;
+use stilts_codegen::context::HtmlContext;
+use stilts_codegen::expand::Graph;
+use stilts_codegen::parse::{TemplateAttrs, TemplateSource};
+
+// print the body of the `fmt` function the derive macro would generate for a template
+pub fn run(config: &Config, path: &str, block: Option<String>) -> Result<ExitCode, Box<dyn Error>> {
+    let attrs = TemplateAttrs {
+        source: TemplateSource::new_file(path),
+        escape: None,
+        trim: None,
+        block,
+        stream: None,
+        locales: Vec::new(),
+    };
+    let graph = Graph::load(config, &attrs)?;
+    let code = graph.expand(config, &mut HtmlContext::default())?;
+
+    // the fields of the template are in scope as variables inside of the real function
+    let writer = &config.writer_name;
+    let file = syn::parse2::<syn::File>(quote! {
+        fn fmt(#writer: &mut (impl ::core::fmt::Write + ?::core::marker::Sized)) -> ::core::fmt::Result {
+            #code
+            Ok(())
+        }
+    })?;
+    print!("{}", prettyplease::unparse(&file));
+    Ok(ExitCode::SUCCESS)
+}
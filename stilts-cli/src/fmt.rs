@@ -0,0 +1,209 @@
+use std::error::Error;
+use std::io::ErrorKind;
+use std::process::ExitCode;
+
+use miette::Report;
+use stilts_codegen::config::Config;
+use stilts_lang::print::PrintRust;
+use stilts_lang::Delims;
+use syn::punctuated::Punctuated;
+use syn::Token;
+
+const INDENT: &str = "    ";
+
+// format the templates in place, or only report the ones which would change with `check`
+pub fn run(config: &Config, paths: &[String], check: bool) -> Result<ExitCode, Box<dyn Error>> {
+    let paths = match paths.is_empty() {
+        true => crate::templates(config)?,
+        false => paths.to_vec(),
+    };
+
+    let mut failed = 0;
+    let mut changed = 0;
+    for name in &paths {
+        let path = config.template_dir.join(name);
+        let source = match std::fs::read_to_string(&path) {
+            Ok(source) => source,
+            // binary files like images can live next to templates
+            Err(e) if e.kind() == ErrorKind::InvalidData => continue,
+            Err(e) => return Err(format!("{e} while reading {path}").into()),
+        };
+        let formatted = match format(&source, &config.delimiters) {
+            Ok(formatted) => formatted,
+            Err(e) => {
+                failed += 1;
+                eprintln!("{name}\n{e:?}");
+                continue;
+            }
+        };
+        if formatted == source {
+            continue;
+        }
+        changed += 1;
+        match check {
+            true => println!("{name}"),
+            false => {
+                std::fs::write(&path, formatted).map_err(|e| format!("{e} while writing {path}"))?
+            }
+        }
+    }
+
+    Ok(match (failed, changed) {
+        (0, 0) => ExitCode::SUCCESS,
+        (0, _) if !check => ExitCode::SUCCESS,
+        _ => ExitCode::FAILURE,
+    })
+}
+
+/// Format a template
+///
+/// The spacing inside of every tag is normalised and the rust code in them is formatted,
+/// the content of the template is left exactly as it is.
+pub fn format(source: &str, delims: &Delims) -> Result<String, Report> {
+    let root = stilts_lang::parse_template(source, delims.clone())
+        .map_err(|e| Report::new(e.into_owned()))?;
+    // the parser skips whitespace at the start of a template
+    let leading = &source[..source.len() - source.trim_start().len()];
+    Ok(format!("{leading}{}", root.to_source_with(delims, &Pretty)))
+}
+
+// prints the rust code in tags with prettyplease
+struct Pretty;
+
+impl PrintRust for Pretty {
+    fn expr(&self, e: &syn::Expr) -> String {
+        expr(e)
+    }
+
+    fn stmt(&self, stmt: &syn::Stmt) -> String {
+        body(vec![stmt.clone()])
+    }
+
+    fn pat(&self, p: &syn::Pat) -> String {
+        pat(p)
+    }
+
+    fn fn_args(&self, args: &Punctuated<syn::FnArg, Token![,]>) -> String {
+        fn_args(args)
+    }
+
+    fn field_values(&self, args: &Punctuated<syn::FieldValue, Token![,]>) -> String {
+        // they are written like the fields of a struct so format them as one
+        let fields = expr(&syn::parse_quote!(S { #args }));
+        fields.strip_prefix("S ").unwrap_or(&fields).to_string()
+    }
+
+    // put the first arm on the next line, indented like the other arms or one level
+    // deeper than the match
+    fn before_arms(&self, indent: &str, arms: Option<&str>) -> String {
+        match arms {
+            Some(arms) => format!("\n{arms}"),
+            None => format!("\n{indent}{INDENT}"),
+        }
+    }
+}
+
+// remove one level of the indentation prettyplease adds inside of a block
+fn dedent(lines: &str) -> String {
+    lines
+        .lines()
+        .map(|line| line.strip_prefix(INDENT).unwrap_or(line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// format statements as the body of a function, then take the body back out
+fn body(stmts: Vec<syn::Stmt>) -> String {
+    let block = syn::Block {
+        brace_token: Default::default(),
+        stmts,
+    };
+    let item: syn::ItemFn = syn::parse_quote!(fn f() #block);
+    let file = syn::File {
+        shebang: None,
+        attrs: Vec::new(),
+        items: vec![syn::Item::Fn(item)],
+    };
+    let out = prettyplease::unparse(&file);
+    let inner = out
+        .strip_prefix("fn f() {\n")
+        .and_then(|out| out.strip_suffix("}\n"))
+        .unwrap_or_default();
+    dedent(inner).trim_end().to_string()
+}
+
+fn expr(expr: &syn::Expr) -> String {
+    body(vec![syn::Stmt::Expr(expr.clone(), None)])
+}
+
+// patterns are formatted as the only arm of a match
+fn pat(pat: &syn::Pat) -> String {
+    let arms = body(vec![syn::parse_quote!(match () { #pat => {} })]);
+    let arm = arms
+        .strip_prefix("match () {\n")
+        .and_then(|arms| arms.strip_suffix("\n}"))
+        .unwrap_or_default();
+    let arm = dedent(arm);
+    arm.strip_suffix(" => {}").unwrap_or(&arm).to_string()
+}
+
+// the arguments of a macro as they would be written in a function signature
+fn fn_args(args: &Punctuated<syn::FnArg, Token![,]>) -> String {
+    let file = syn::File {
+        shebang: None,
+        attrs: Vec::new(),
+        items: vec![syn::parse_quote!(fn f(#args) {})],
+    };
+    let out = prettyplease::unparse(&file);
+    let args = out
+        .strip_prefix("fn f(")
+        .and_then(|out| out.strip_suffix(") {}\n"))
+        .unwrap_or_default();
+    // long signatures are split over lines with a trailing comma
+    let args = args.lines().map(str::trim).collect::<Vec<_>>().join(" ");
+    args.trim_end_matches(',').trim().to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::format;
+    use stilts_lang::{parse_template, Delims};
+
+    #[test]
+    fn normalises_tags() {
+        let source = "  <ul>{%for x in items.iter( )%}\n  <li>{%x.name%}</li>{%end%}</ul>\n\
+            {%match  value%}{%when Some( v )if v>1%}big{%when _%}small{% end%}\n";
+        let formatted = format(source, &Delims::default()).unwrap();
+        assert_eq!(
+            formatted,
+            "  <ul>{% for x in items.iter() %}\n  <li>{% x.name %}</li>{% end %}</ul>\n\
+            {% match value %}\n    {% when Some(v) if v > 1 %}big{% when _ %}small{% end %}\n"
+        );
+        assert_eq!(format(&formatted, &Delims::default()).unwrap(), formatted);
+    }
+
+    #[test]
+    fn keeps_arm_indentation() {
+        let source = "<div>\n  {%match value%}\n    {%when Some(v)%}{%v%}\n    {%when None%}none\n  {%end%}\n</div>\n\
+            {% match x %}\n  {% when 1 %}one\n  {% when _ %}other\n{% end %}\n";
+        let formatted = format(source, &Delims::default()).unwrap();
+        assert_eq!(
+            formatted,
+            "<div>\n  {% match value %}\n    {% when Some(v) %}{% v %}\n    {% when None %}none\n  {% end %}\n</div>\n\
+            {% match x %}\n  {% when 1 %}one\n  {% when _ %}other\n{% end %}\n"
+        );
+        assert_eq!(format(&formatted, &Delims::default()).unwrap(), formatted);
+    }
+
+    #[test]
+    fn round_trip() {
+        let source = r#"{%extends "base.html"%}{%block main%}{%include "a \"b\".html" {x:1,y}%}
+{%macro row(name:&str,n:u8)%}{%name%}{%end%}{%call row("x",1)%}{% let total = 1+2; %}
+{%if a%}1{%else if b%}2{%else%}3{%end%}{%super()%}{%end%}"#;
+        let formatted = format(source, &Delims::default()).unwrap();
+        assert_eq!(
+            parse_template(&formatted, Delims::default()).unwrap(),
+            parse_template(source, Delims::default()).unwrap()
+        );
+    }
+}
@@ -0,0 +1,141 @@
+//! The stilts command line tool for checking and inspecting templates
+
+#![forbid(unsafe_code)]
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::{Parser, Subcommand};
+use stilts_codegen::config::Config;
+
+mod ast;
+mod check;
+mod expand;
+mod fmt;
+mod tree;
+
+#[derive(Parser)]
+#[command(name = "stilts", version, about)]
+struct Cli {
+    /// Path to the Cargo.toml of the project, defaults to the closest one
+    #[arg(long, global = true)]
+    manifest_path: Option<PathBuf>,
+    /// The package in the workspace whose templates are used
+    #[arg(short, long, global = true)]
+    package: Option<String>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Parse every template in the template directory and report any errors
+    Check,
+    /// Print the templates each template extends and includes
+    Tree {
+        /// A template relative to the template directory, defaults to all of them
+        path: Option<String>,
+    },
+    /// Format templates in place, the content around tags is left exactly as it is
+    Fmt {
+        /// Templates relative to the template directory, defaults to all of them
+        paths: Vec<String>,
+        /// Only list the templates which are not formatted and fail if there are any
+        #[arg(long)]
+        check: bool,
+    },
+    /// Print the rust code generated for a template
+    Expand {
+        /// The template relative to the template directory
+        path: String,
+        /// Only expand a single block like the `block` attribute of the derive macro
+        #[arg(long)]
+        block: Option<String>,
+    },
+    /// Print the syntax tree of templates as json
+    Ast {
+        /// A template relative to the template directory, defaults to all of them
+        path: Option<String>,
+    },
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let config = match load_config(&cli) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("error: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let res = match cli.command {
+        Command::Check => check::run(&config),
+        Command::Tree { path } => tree::run(&config, path.as_deref()),
+        Command::Fmt { paths, check } => fmt::run(&config, &paths, check),
+        Command::Expand { path, block } => expand::run(&config, &path, block),
+        Command::Ast { path } => ast::run(&config, path.as_deref()),
+    };
+    match res {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn load_config(cli: &Cli) -> Result<Config, Box<dyn std::error::Error>> {
+    let manifest = match &cli.manifest_path {
+        Some(path) => path.clone(),
+        None => find_manifest()?,
+    };
+    let manifest = camino(manifest)?;
+    Ok(Config::load_from(&manifest, cli.package.as_deref())?)
+}
+
+// the closest Cargo.toml in the current directory or any of its parents like cargo
+fn find_manifest() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let cwd = std::env::current_dir()?;
+    cwd.ancestors()
+        .map(|dir| dir.join("Cargo.toml"))
+        .find(|manifest| manifest.is_file())
+        .ok_or_else(|| {
+            format!(
+                "could not find Cargo.toml in {} or any parent",
+                cwd.display()
+            )
+            .into()
+        })
+}
+
+fn camino(path: PathBuf) -> Result<Utf8PathBuf, Box<dyn std::error::Error>> {
+    Utf8PathBuf::from_path_buf(path)
+        .map_err(|path| format!("{} is not valid UTF-8", path.display()).into())
+}
+
+// every file in the template directory relative to it, sorted so output is stable
+fn templates(config: &Config) -> std::io::Result<Vec<String>> {
+    fn walk(dir: &Utf8Path, out: &mut Vec<Utf8PathBuf>) -> std::io::Result<()> {
+        for entry in dir.read_dir_utf8()? {
+            let path = entry?.into_path();
+            if path.is_dir() {
+                walk(&path, out)?;
+            } else {
+                out.push(path);
+            }
+        }
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+    walk(&config.template_dir, &mut files)?;
+    let mut files: Vec<_> = files
+        .iter()
+        .filter_map(|f| f.strip_prefix(&config.template_dir).ok())
+        .map(|f| f.as_str().replace('\\', "/"))
+        .collect();
+    files.sort();
+    Ok(files)
+}
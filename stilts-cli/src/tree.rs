@@ -0,0 +1,104 @@
+use std::error::Error;
+use std::process::ExitCode;
+
+use stilts_codegen::config::Config;
+use stilts_lang::types::Expr;
+use stilts_lang::visit::Visit;
+
+// how a template depends on another one
+struct Dependency {
+    kind: &'static str,
+    path: String,
+}
+
+// why the dependencies of a template are unknown
+enum Missing {
+    NotFound,
+    Unreadable,
+    ParseError,
+}
+
+impl std::fmt::Display for Missing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::NotFound => "not found",
+            Self::Unreadable => "unreadable",
+            Self::ParseError => "parse error",
+        })
+    }
+}
+
+// the templates a template depends on in the order they appear
+fn dependencies(config: &Config, path: &str) -> Result<Vec<Dependency>, Missing> {
+    let content =
+        std::fs::read_to_string(config.template_dir.join(path)).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => Missing::NotFound,
+            _ => Missing::Unreadable,
+        })?;
+    let root = stilts_lang::parse_template(&content, config.delimiters.clone())
+        .map_err(|_| Missing::ParseError)?;
+    let mut deps = Collect(Vec::new());
+    deps.visit_root(&root);
+    Ok(deps.0)
+}
+
+// the extends and includes anywhere in a template
+struct Collect(Vec<Dependency>);
+
+impl<'ast, 'i> Visit<'ast, 'i> for Collect {
+    fn visit_expr(&mut self, expr: &'ast Expr<'i>) {
+        let (kind, path) = match expr {
+            Expr::Extends(path) => ("extends", path),
+            Expr::Include { reference, .. } => ("include", reference),
+            _ => return,
+        };
+        self.0.push(Dependency {
+            kind,
+            path: path.to_string(),
+        });
+    }
+}
+
+// print the dependencies of a template below it, `stack` holds the templates above
+// it so cycles are only printed once
+fn print(config: &Config, deps: &[Dependency], prefix: &str, stack: &mut Vec<String>) {
+    for (i, dep) in deps.iter().enumerate() {
+        let (branch, indent) = match i + 1 == deps.len() {
+            true => ("└── ", "    "),
+            false => ("├── ", "│   "),
+        };
+        let line = format!("{prefix}{branch}{} {}", dep.kind, dep.path);
+        if stack.contains(&dep.path) {
+            println!("{line} (cycle)");
+            continue;
+        }
+        match dependencies(config, &dep.path) {
+            Ok(next) => {
+                println!("{line}");
+                stack.push(dep.path.clone());
+                print(config, &next, &format!("{prefix}{indent}"), stack);
+                stack.pop();
+            }
+            Err(e) => println!("{line} ({e})"),
+        }
+    }
+}
+
+pub fn run(config: &Config, path: Option<&str>) -> Result<ExitCode, Box<dyn Error>> {
+    let roots = match path {
+        Some(path) => vec![path.to_string()],
+        None => crate::templates(config)?,
+    };
+    for root in roots {
+        match dependencies(config, &root) {
+            Ok(deps) => {
+                println!("{root}");
+                print(config, &deps, "", &mut vec![root.clone()]);
+            }
+            // binary files like images can live next to templates
+            Err(Missing::Unreadable) if path.is_none() => {}
+            Err(e) => println!("{root} ({e})"),
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,62 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Ident, ImplGenerics, TypeGenerics, WhereClause};
+
+pub(crate) const FLUSH_MACRO: &str = "__stilts_flush";
+
+// the point after static content at which the async buffer may be written out
+pub fn flush_point() -> TokenStream {
+    let flush = Ident::new(FLUSH_MACRO, proc_macro2::Span::call_site());
+    quote! { #flush!(); }
+}
+
+// defines flush points as doing nothing, this is used in the synchronous code
+// and inside of template macros which are always synchronous
+pub fn flush_noop() -> TokenStream {
+    let flush = Ident::new(FLUSH_MACRO, proc_macro2::Span::call_site());
+    quote! {
+        #[allow(unused_macros)]
+        macro_rules! #flush {
+            () => {};
+        }
+    }
+}
+
+pub fn expand_async_template(
+    ident: &Ident,
+    impl_gen: &ImplGenerics,
+    type_gen: &TypeGenerics,
+    where_clause: &Option<&WhereClause>,
+    writer: &Ident,
+    prelude: &TokenStream,
+    template_code: &TokenStream,
+) -> TokenStream {
+    let flush = Ident::new(FLUSH_MACRO, proc_macro2::Span::call_site());
+    quote! {
+        impl #impl_gen ::stilts::async_io::AsyncTemplate for #ident #type_gen #where_clause {
+            async fn write_async<S>(&self, sink: &mut S) -> ::std::io::Result<()>
+            where
+                S: ::stilts::async_io::AsyncSink + ?::core::marker::Sized,
+            {
+                let mut buffer = ::stilts::async_io::AsyncBuffer::new(sink, ::stilts::async_io::DEFAULT_FLUSH_SIZE);
+                let res: ::core::result::Result<(), ::stilts::async_io::RenderError> = async {
+                    let #writer = &mut buffer;
+                    #[allow(unused_macros)]
+                    macro_rules! #flush {
+                        () => {
+                            if #writer.should_flush() {
+                                #writer.flush().await?;
+                            }
+                        };
+                    }
+                    #prelude
+
+                    #template_code
+                    Ok(())
+                }.await;
+                res?;
+                buffer.finish().await
+            }
+        }
+    }
+}
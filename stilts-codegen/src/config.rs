@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+
+use cargo_metadata::{
+    camino::{Utf8Path, Utf8PathBuf},
+    MetadataCommand,
+};
+use globset::{GlobBuilder, GlobMatcher};
+use serde::{Deserialize, Deserializer};
+use stilts_lang::Delims;
+
+use crate::{err, pathing::expand_path};
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub template_dir: Utf8PathBuf,
+    pub trim: bool,
+    pub contextual_escape: bool,
+    pub template_suffixes: Vec<String>,
+    #[serde(deserialize_with = "Config::deserialize_delims")]
+    pub delimiters: Delims,
+    #[serde(deserialize_with = "Config::deserialize_writer_name")]
+    pub writer_name: syn::Ident,
+    #[serde(
+        rename = "escape",
+        deserialize_with = "Config::deserialize_escape_table"
+    )]
+    escape_table: EscapeTable,
+    pub i18n_dir: Utf8PathBuf,
+    pub default_locale: String,
+    // only loaded once a template uses a translation
+    #[cfg(feature = "i18n")]
+    #[serde(skip)]
+    pub catalog: std::cell::OnceCell<crate::i18n::Catalog>,
+}
+
+// escapers keyed by what they match against, extensions may be compound like `html.j2`
+#[derive(Default)]
+struct EscapeTable {
+    extensions: HashMap<String, syn::Path>,
+    mimes: HashMap<String, syn::Path>,
+    globs: Vec<(GlobMatcher, syn::Path)>,
+}
+
+impl EscapeTable {
+    // globs contain one of `*?[`, mime types contain a `/`, anything else is an extension
+    fn insert(&mut self, pattern: &str, path: syn::Path) -> Result<(), globset::Error> {
+        if pattern.contains(['*', '?', '[']) {
+            // `*` stays inside of one directory like it does in a shell, `**` crosses them
+            let glob = GlobBuilder::new(pattern)
+                .literal_separator(true)
+                .build()?
+                .compile_matcher();
+            self.globs.retain(|(g, _)| g.glob().glob() != pattern);
+            self.globs.push((glob, path));
+            // prefer the most specific glob
+            self.globs
+                .sort_by_key(|(g, _)| std::cmp::Reverse(g.glob().glob().len()));
+        } else if pattern.contains('/') {
+            self.mimes.insert(pattern.to_ascii_lowercase(), path);
+        } else {
+            self.extensions
+                .insert(pattern.trim_start_matches('.').to_string(), path);
+        }
+        Ok(())
+    }
+}
+
+impl Config {
+    /// Load the config of the crate being compiled
+    pub fn load() -> syn::Result<Self> {
+        let dir = std::env::var("CARGO_MANIFEST_DIR").map_err(|e| err!(e))?;
+        let pkg = std::env::var("CARGO_PKG_NAME").map_err(|e| err!(e))?;
+        Self::load_from(&Utf8PathBuf::from(dir).join("Cargo.toml"), Some(&pkg))
+    }
+
+    /// Load the config of a package in the workspace of a manifest, the root package of the
+    /// manifest is used when no package is named
+    pub fn load_from(manifest: &Utf8Path, package: Option<&str>) -> syn::Result<Self> {
+        let metadata = MetadataCommand::new()
+            .manifest_path(manifest)
+            .exec()
+            .map_err(|e| err!(e))?;
+
+        let pkg = match package {
+            Some(name) => metadata.packages.iter().find(|p| p.name == name),
+            None => metadata.root_package(),
+        };
+        let pkg = pkg.ok_or_else(|| match package {
+            Some(name) => err!(format!("package `{name}` not found in {manifest}")),
+            None => err!(format!(
+                "{manifest} has no root package, name one of its members"
+            )),
+        })?;
+        let manifest_dir = pkg.manifest_path.parent().unwrap_or(Utf8Path::new(""));
+
+        let meta = pkg.metadata.as_object().and_then(|meta| meta.get("stilts"));
+        let mut config = match meta {
+            Some(meta) => Config::deserialize(meta).map_err(|e| err!(e))?,
+            None => Config::default(),
+        };
+        config.template_dir = expand_path(config.template_dir, manifest_dir);
+        config.i18n_dir = expand_path(config.i18n_dir, manifest_dir);
+        Ok(config)
+    }
+
+    // find the escaper for a template file, globs are checked first then the longest
+    // matching extension, the extension without the template suffix, and finally the mime type
+    pub fn escaper(&self, path: Option<&Utf8Path>) -> syn::Path {
+        path.and_then(|path| {
+            let table = &self.escape_table;
+            let relative = path.strip_prefix(&self.template_dir).unwrap_or(path);
+            let name = path.file_name()?;
+            let by_extension = |name: &str| {
+                name.match_indices('.')
+                    .filter(|(i, _)| *i != 0)
+                    .find_map(|(i, _)| table.extensions.get(&name[i + 1..]))
+            };
+
+            table
+                .globs
+                .iter()
+                .find(|(glob, _)| glob.is_match(relative))
+                .map(|(_, path)| path)
+                .or_else(|| by_extension(name))
+                .or_else(|| by_extension(self.strip_suffix(name)))
+                .or_else(|| {
+                    let mime = self.mime_type(path.as_str())?;
+                    table.mimes.get(mime.essence_str())
+                })
+                .cloned()
+        })
+        .unwrap_or_else(|| syn::parse_str("::stilts::escaping::Empty").unwrap())
+    }
+
+    // the messages of the default locale, they are read the first time this is called
+    #[cfg(feature = "i18n")]
+    pub fn catalog(&self) -> syn::Result<&crate::i18n::Catalog> {
+        if let Some(catalog) = self.catalog.get() {
+            return Ok(catalog);
+        }
+        let catalog = crate::i18n::Catalog::load(self)?;
+        Ok(self.catalog.get_or_init(|| catalog))
+    }
+
+    // guess the mime type of a template path ignoring any template suffix
+    pub fn mime_type(&self, path: &str) -> Option<mime_guess::mime::Mime> {
+        mime_guess::from_path(self.strip_suffix(path)).first()
+    }
+
+    // remove a template suffix like `.j2` from the end of a file name
+    fn strip_suffix<'a>(&self, name: &'a str) -> &'a str {
+        self.template_suffixes
+            .iter()
+            .find_map(|suffix| {
+                name.strip_suffix(suffix.as_str())
+                    .and_then(|name| name.strip_suffix('.'))
+            })
+            .unwrap_or(name)
+    }
+
+    fn deserialize_delims<'de, D>(deserializer: D) -> Result<Delims, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        <[String; 2]>::deserialize(deserializer)
+            .map(|[open, close]| Delims::new(open, close))
+    }
+
+    fn deserialize_writer_name<'de, D>(deserializer: D) -> Result<syn::Ident, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        <&str>::deserialize(deserializer)
+            .map(|s| syn::Ident::new(s, proc_macro2::Span::call_site()))
+    }
+
+    // extend the default table with the parsed version users can overwrite things if they want
+    fn deserialize_escape_table<'de, D>(deserializer: D) -> Result<EscapeTable, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let mut table = Self::default_escape_table();
+        let parsed = HashMap::<String, Vec<String>>::deserialize(deserializer)?;
+
+        for (path, patterns) in parsed {
+            let path: syn::Path = syn::parse_str(&path)
+                .map_err(<D::Error as serde::de::Error>::custom)?;
+            for pattern in patterns {
+                table
+                    .insert(&pattern, path.clone())
+                    .map_err(<D::Error as serde::de::Error>::custom)?;
+            }
+        }
+        Ok(table)
+    }
+
+    fn default_escape_table() -> EscapeTable {
+        let mut table = EscapeTable::default();
+        for (pattern, path) in [
+            ("html", "::stilts::escaping::Html"),
+            ("htm", "::stilts::escaping::Html"),
+            ("js", "::stilts::escaping::JsString"),
+            ("mjs", "::stilts::escaping::JsString"),
+            ("json", "::stilts::escaping::Json"),
+            ("xml", "::stilts::escaping::Xml"),
+            ("svg", "::stilts::escaping::Xml"),
+            ("tex", "::stilts::escaping::Latex"),
+            ("sh", "::stilts::escaping::Shell"),
+            ("text/html", "::stilts::escaping::Html"),
+            ("application/json", "::stilts::escaping::Json"),
+            ("application/xml", "::stilts::escaping::Xml"),
+            ("text/xml", "::stilts::escaping::Xml"),
+            ("application/rss+xml", "::stilts::escaping::Xml"),
+            ("application/atom+xml", "::stilts::escaping::Xml"),
+        ] {
+            table.insert(pattern, syn::parse_str(path).unwrap()).unwrap();
+        }
+        table
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            template_dir: "$CARGO_MANIFEST_DIR/templates".into(),
+            trim: false,
+            contextual_escape: true,
+            template_suffixes: ["stilts", "j2", "jinja", "jinja2", "tmpl"]
+                .map(String::from)
+                .to_vec(),
+            delimiters: Delims::default(),
+            writer_name: syn::Ident::new("_w", proc_macro2::Span::call_site()),
+            escape_table: Self::default_escape_table(),
+            i18n_dir: "$CARGO_MANIFEST_DIR/locales".into(),
+            default_locale: "en".to_string(),
+            #[cfg(feature = "i18n")]
+            catalog: Default::default(),
+        }
+    }
+}
@@ -0,0 +1,597 @@
+//! Tracks where in an HTML document each template expression ends up
+//!
+//! The static content of a template is scanned in render order, this gives the context
+//! of every expression so that it can be escaped correctly. Expressions inside of
+//! `<script>`, `<style>`, event handlers, and URL attributes need different escaping
+//! than regular text, and some places (like a tag name) can not be escaped at all.
+
+/// How an expression should be escaped in its context
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Escape {
+    /// Use the escaper configured for the template
+    Default,
+    /// Use a specific escaper from `::stilts::escaping`
+    With(&'static str),
+    /// The value can not be escaped here, so it must already be safe
+    RequireSafe,
+    /// Like `RequireSafe` but values which are only safe in a specific part of
+    /// the template from `::stilts::escaping` are allowed as well
+    RequireSafeIn(&'static str),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Element {
+    Script,
+    Style,
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Quote {
+    Double,
+    Single,
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UrlPart {
+    Start,
+    Path,
+    Query,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Js {
+    // `regex` is whether a `/` here starts a regular expression rather than a division
+    Code { regex: bool },
+    Str { quote: char, escaped: bool },
+    Regex { class: bool, escaped: bool },
+    LineComment,
+    BlockComment,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Css {
+    Code,
+    Str { quote: char, escaped: bool },
+    Comment,
+}
+
+// the language inside of an attribute value or raw text element
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Lang {
+    Html,
+    Url(UrlPart),
+    Js(Js),
+    Css(Css),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum State {
+    Text,
+    Comment,
+    // just after a `<` reading the tag name
+    TagOpen { closing: bool, name: String },
+    // inside a tag between attributes
+    Tag { element: Element, closing: bool },
+    AttrName { element: Element, closing: bool, name: String },
+    AfterAttrName { element: Element, closing: bool, name: String },
+    BeforeValue { element: Element, closing: bool, lang: Lang },
+    Value { element: Element, closing: bool, lang: Lang, quote: Quote },
+    // the contents of a script or style element
+    RawText { element: Element, lang: Lang },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HtmlContext {
+    state: State,
+}
+
+const URL_ATTRS: &[&str] = &[
+    "action",
+    "archive",
+    "background",
+    "cite",
+    "classid",
+    "codebase",
+    "data",
+    "formaction",
+    "href",
+    "icon",
+    "longdesc",
+    "manifest",
+    "ping",
+    "poster",
+    "profile",
+    "src",
+    "srcset",
+    "usemap",
+    "xlink:href",
+];
+
+impl Default for HtmlContext {
+    fn default() -> Self {
+        Self { state: State::Text }
+    }
+}
+
+impl HtmlContext {
+    /// Scan static content that is written in the current context
+    ///
+    /// Errors for content that can not be tracked reliably.
+    pub fn advance(&mut self, content: &str) -> Result<(), &'static str> {
+        let mut rest = content;
+        while let Some(c) = rest.chars().next() {
+            let before = &content[..content.len() - rest.len()];
+            rest = &rest[c.len_utf8()..];
+            rest = self.step(c, before, rest)?;
+        }
+        Ok(())
+    }
+
+    /// How an expression written in the current context must be escaped
+    pub fn escape(&self) -> Escape {
+        match &self.state {
+            State::Text | State::Comment => Escape::Default,
+            State::TagOpen { .. }
+            | State::Tag { .. }
+            | State::AttrName { .. }
+            | State::AfterAttrName { .. } => Escape::RequireSafe,
+            State::BeforeValue { lang, .. } => Self::escape_lang(*lang, Quote::None),
+            State::Value { lang, quote, .. } => Self::escape_lang(*lang, *quote),
+            // only code directly inside of a script can not end an attribute early
+            State::RawText {
+                lang: Lang::Js(Js::Code { .. }),
+                ..
+            } => Escape::RequireSafeIn("Script"),
+            State::RawText { lang, .. } => Self::escape_lang(*lang, Quote::Double),
+        }
+    }
+
+    /// Update the context after an expression has been written
+    pub fn after_expr(&mut self) {
+        match &mut self.state {
+            State::BeforeValue {
+                element,
+                closing,
+                lang,
+            } => {
+                let lang = match *lang {
+                    Lang::Url(_) => Lang::Url(UrlPart::Path),
+                    lang => lang,
+                };
+                self.state = State::Value {
+                    element: *element,
+                    closing: *closing,
+                    lang,
+                    quote: Quote::None,
+                };
+            }
+            State::Value {
+                lang: Lang::Url(part @ UrlPart::Start),
+                ..
+            } => *part = UrlPart::Path,
+            // a `/` after a value is a division
+            State::Value {
+                lang: Lang::Js(Js::Code { regex }),
+                ..
+            }
+            | State::RawText {
+                lang: Lang::Js(Js::Code { regex }),
+                ..
+            } => *regex = false,
+            _ => {}
+        }
+    }
+
+    /// Combine the contexts at the end of two branches
+    ///
+    /// Branches of a URL may have reached different parts of it, in that case the
+    /// strictest escaping is used afterwards. Any other difference is an error.
+    pub fn join(&self, other: &Self) -> Option<Self> {
+        if self == other {
+            return Some(self.clone());
+        }
+        let parts = Self::url_value(&self.state)?;
+        if parts != Self::url_value(&other.state)? {
+            return None;
+        }
+        let mut joined = self.clone();
+        if let State::Value {
+            lang: Lang::Url(part),
+            ..
+        } = &mut joined.state
+        {
+            *part = UrlPart::Query;
+        }
+        Some(joined)
+    }
+
+    /// A description of the context for error messages
+    pub fn describe(&self) -> &'static str {
+        match &self.state {
+            State::Text => "text",
+            State::Comment => "a comment",
+            State::TagOpen { .. } => "a tag name",
+            State::Tag { .. } | State::AttrName { .. } | State::AfterAttrName { .. } => {
+                "a tag"
+            }
+            State::BeforeValue { .. } | State::Value { .. } => "an attribute value",
+            State::RawText {
+                element: Element::Script,
+                ..
+            } => "a script",
+            State::RawText { .. } => "a style element",
+        }
+    }
+
+    // the parts of a url attribute value which must match when joining, ignoring the url part
+    fn url_value(state: &State) -> Option<State> {
+        match state {
+            State::Value {
+                element,
+                closing,
+                lang: Lang::Url(_),
+                quote,
+            } => Some(State::Value {
+                element: *element,
+                closing: *closing,
+                lang: Lang::Url(UrlPart::Start),
+                quote: *quote,
+            }),
+            _ => None,
+        }
+    }
+
+    fn escape_lang(lang: Lang, quote: Quote) -> Escape {
+        match (lang, quote) {
+            (Lang::Html, Quote::None) => Escape::With("HtmlAttr"),
+            (Lang::Html, _) => Escape::Default,
+            (Lang::Url(UrlPart::Start), _) => Escape::With("Url"),
+            (Lang::Url(UrlPart::Path), _) => Escape::With("UrlPath"),
+            (Lang::Url(UrlPart::Query), _) => Escape::With("UrlQuery"),
+            (Lang::Js(Js::Code { .. } | Js::Regex { .. }), _) | (Lang::Css(Css::Code), _) => {
+                Escape::RequireSafe
+            }
+            (Lang::Js(_), _) => Escape::With("JsString"),
+            (Lang::Css(_), _) => Escape::With("CssString"),
+        }
+    }
+
+    fn attr_lang(name: &str) -> Lang {
+        if name.starts_with("on") {
+            Lang::Js(Js::Code { regex: true })
+        } else if name == "style" {
+            Lang::Css(Css::Code)
+        } else if URL_ATTRS.contains(&name) {
+            Lang::Url(UrlPart::Start)
+        } else {
+            Lang::Html
+        }
+    }
+
+    // the state after the `>` closing a tag
+    fn end_tag(element: Element, closing: bool) -> State {
+        match (element, closing) {
+            (Element::Script, false) => State::RawText {
+                element,
+                lang: Lang::Js(Js::Code { regex: true }),
+            },
+            (Element::Style, false) => State::RawText {
+                element,
+                lang: Lang::Css(Css::Code),
+            },
+            _ => State::Text,
+        }
+    }
+
+    // handle a single character, `before` is the content before it and `rest` is the
+    // content after it which is returned with anything that was consumed as lookahead removed
+    fn step<'r>(&mut self, c: char, before: &str, rest: &'r str) -> Result<&'r str, &'static str> {
+        match &mut self.state {
+            State::Text => {
+                if c == '<' {
+                    if let Some(rest) = rest.strip_prefix("!--") {
+                        self.state = State::Comment;
+                        return Ok(rest);
+                    }
+                    self.state = State::TagOpen {
+                        closing: false,
+                        name: String::new(),
+                    };
+                }
+            }
+            State::Comment => {
+                if c == '-' {
+                    if let Some(rest) = rest.strip_prefix("->") {
+                        self.state = State::Text;
+                        return Ok(rest);
+                    }
+                }
+            }
+            State::TagOpen { closing, name } => match c {
+                '/' if name.is_empty() && !*closing => *closing = true,
+                // doctypes and processing instructions are treated like closing tags
+                '!' | '?' if name.is_empty() => {
+                    self.state = State::Tag {
+                        element: Element::Other,
+                        closing: true,
+                    }
+                }
+                c if c.is_ascii_alphanumeric() || (!name.is_empty() && "-:_".contains(c)) => {
+                    name.push(c.to_ascii_lowercase())
+                }
+                _ if name.is_empty() => {
+                    // not a tag after all, a stray `<` in text
+                    self.state = State::Text;
+                    return self.step(c, before, rest);
+                }
+                c => {
+                    let element = match name.as_str() {
+                        "script" => Element::Script,
+                        "style" => Element::Style,
+                        _ => Element::Other,
+                    };
+                    self.state = State::Tag {
+                        element,
+                        closing: *closing,
+                    };
+                    return self.step(c, before, rest);
+                }
+            },
+            State::Tag { element, closing } => match c {
+                '>' => self.state = Self::end_tag(*element, *closing),
+                c if c.is_whitespace() || c == '/' => {}
+                c => {
+                    self.state = State::AttrName {
+                        element: *element,
+                        closing: *closing,
+                        name: c.to_ascii_lowercase().to_string(),
+                    }
+                }
+            },
+            State::AttrName {
+                element,
+                closing,
+                name,
+            } => match c {
+                '>' => self.state = Self::end_tag(*element, *closing),
+                '=' => {
+                    self.state = State::BeforeValue {
+                        element: *element,
+                        closing: *closing,
+                        lang: Self::attr_lang(name),
+                    }
+                }
+                '/' => {
+                    self.state = State::Tag {
+                        element: *element,
+                        closing: *closing,
+                    }
+                }
+                c if c.is_whitespace() => {
+                    self.state = State::AfterAttrName {
+                        element: *element,
+                        closing: *closing,
+                        name: std::mem::take(name),
+                    }
+                }
+                c => name.push(c.to_ascii_lowercase()),
+            },
+            State::AfterAttrName {
+                element,
+                closing,
+                name,
+            } => match c {
+                '>' => self.state = Self::end_tag(*element, *closing),
+                '=' => {
+                    self.state = State::BeforeValue {
+                        element: *element,
+                        closing: *closing,
+                        lang: Self::attr_lang(name),
+                    }
+                }
+                c if c.is_whitespace() => {}
+                _ => {
+                    self.state = State::Tag {
+                        element: *element,
+                        closing: *closing,
+                    };
+                    return self.step(c, before, rest);
+                }
+            },
+            State::BeforeValue {
+                element,
+                closing,
+                lang,
+            } => {
+                let quote = match c {
+                    '>' => {
+                        self.state = Self::end_tag(*element, *closing);
+                        return Ok(rest);
+                    }
+                    c if c.is_whitespace() => return Ok(rest),
+                    '"' => Quote::Double,
+                    '\'' => Quote::Single,
+                    _ => Quote::None,
+                };
+                self.state = State::Value {
+                    element: *element,
+                    closing: *closing,
+                    lang: *lang,
+                    quote,
+                };
+                if quote == Quote::None {
+                    return self.step(c, before, rest);
+                }
+            }
+            State::Value {
+                element,
+                closing,
+                lang,
+                quote,
+            } => match (c, *quote) {
+                ('"', Quote::Double) | ('\'', Quote::Single) => {
+                    self.state = State::Tag {
+                        element: *element,
+                        closing: *closing,
+                    }
+                }
+                ('>', Quote::None) => self.state = Self::end_tag(*element, *closing),
+                (c, Quote::None) if c.is_whitespace() => {
+                    self.state = State::Tag {
+                        element: *element,
+                        closing: *closing,
+                    }
+                }
+                (c, _) => return Self::step_lang(lang, c, before, rest),
+            },
+            State::RawText { element, lang } => {
+                let end = match element {
+                    Element::Script => "/script",
+                    _ => "/style",
+                };
+                let is_end = c == '<'
+                    && rest
+                        .get(..end.len())
+                        .is_some_and(|s| s.eq_ignore_ascii_case(end));
+                if is_end {
+                    self.state = State::TagOpen {
+                        closing: true,
+                        name: String::new(),
+                    };
+                    return Ok(&rest[1..]);
+                }
+                return Self::step_lang(lang, c, before, rest);
+            }
+        }
+        Ok(rest)
+    }
+
+    fn step_lang<'r>(
+        lang: &mut Lang,
+        c: char,
+        before: &str,
+        rest: &'r str,
+    ) -> Result<&'r str, &'static str> {
+        match lang {
+            Lang::Html => {}
+            Lang::Url(part) => {
+                *part = match c {
+                    '?' | '#' => UrlPart::Query,
+                    _ if *part == UrlPart::Start => UrlPart::Path,
+                    _ => *part,
+                }
+            }
+            Lang::Js(js) => match js {
+                Js::Code { regex } => match c {
+                    '"' | '\'' | '`' => {
+                        *js = Js::Str {
+                            quote: c,
+                            escaped: false,
+                        }
+                    }
+                    '/' if rest.starts_with('/') => {
+                        *js = Js::LineComment;
+                        return Ok(&rest[1..]);
+                    }
+                    '/' if rest.starts_with('*') => {
+                        *js = Js::BlockComment;
+                        return Ok(&rest[1..]);
+                    }
+                    '/' if *regex || ends_with_keyword(before) => {
+                        *js = Js::Regex {
+                            class: false,
+                            escaped: false,
+                        }
+                    }
+                    c if c.is_whitespace() => {}
+                    c => *regex = !(c.is_alphanumeric() || "_$)]".contains(c)),
+                },
+                Js::Str { quote, escaped } => {
+                    if *escaped {
+                        *escaped = false;
+                    } else if c == '\\' {
+                        *escaped = true;
+                    } else if c == *quote {
+                        *js = Js::Code { regex: false };
+                    } else if *quote == '`' && c == '$' && rest.starts_with('{') {
+                        return Err("substitutions in JavaScript template literals are not supported");
+                    }
+                }
+                Js::Regex { class, escaped } => {
+                    if *escaped {
+                        *escaped = false;
+                    } else if c == '\\' {
+                        *escaped = true;
+                    } else if c == '[' {
+                        *class = true;
+                    } else if c == ']' {
+                        *class = false;
+                    } else if c == '/' && !*class {
+                        *js = Js::Code { regex: false };
+                    } else if c == '\n' {
+                        return Err("unterminated JavaScript regular expression");
+                    }
+                }
+                Js::LineComment => {
+                    if c == '\n' {
+                        *js = Js::Code { regex: true };
+                    }
+                }
+                Js::BlockComment => {
+                    if c == '*' && rest.starts_with('/') {
+                        *js = Js::Code { regex: true };
+                        return Ok(&rest[1..]);
+                    }
+                }
+            },
+            Lang::Css(css) => match css {
+                Css::Code => match c {
+                    '"' | '\'' => {
+                        *css = Css::Str {
+                            quote: c,
+                            escaped: false,
+                        }
+                    }
+                    '/' if rest.starts_with('*') => {
+                        *css = Css::Comment;
+                        return Ok(&rest[1..]);
+                    }
+                    _ => {}
+                },
+                Css::Str { quote, escaped } => {
+                    if *escaped {
+                        *escaped = false;
+                    } else if c == '\\' {
+                        *escaped = true;
+                    } else if c == *quote || c == '\n' {
+                        *css = Css::Code;
+                    }
+                }
+                Css::Comment => {
+                    if c == '*' && rest.starts_with('/') {
+                        *css = Css::Code;
+                        return Ok(&rest[1..]);
+                    }
+                }
+            },
+        }
+        Ok(rest)
+    }
+}
+
+// whether a script ends with a keyword that a regular expression can follow
+fn ends_with_keyword(script: &str) -> bool {
+    const KEYWORDS: &[&str] = &[
+        "await", "case", "delete", "do", "else", "in", "instanceof", "new", "of", "return",
+        "throw", "typeof", "void", "yield",
+    ];
+    let script = script.trim_end();
+    let word = script
+        .rsplit(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+        .next()
+        .unwrap_or_default();
+    let prev = script[..script.len() - word.len()].chars().next_back();
+    KEYWORDS.contains(&word) && prev != Some('.')
+}
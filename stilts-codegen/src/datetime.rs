@@ -0,0 +1,168 @@
+//! Checks the format strings of the date formatting methods of `DateTimeExt`
+//!
+//! A string literal passed as the format of `strftime` or `strftime_in` is checked against
+//! the specifiers the stilts crate supports, so an unsupported one is a compile error pointing
+//! at the format instead of failing to render. Formats which are not literals are only
+//! checked when rendering.
+
+use stilts_lang::types::{Expr, IfBranch, ItemFor, ItemIf, ItemMatch, MatchArm, Root};
+use stilts_lang::visit::{self, Visit};
+use syn::visit::Visit as SynVisit;
+
+use crate::err;
+
+/// Check the date formats in every expression of a template
+pub fn check(root: &Root<'_>) -> syn::Result<()> {
+    let mut dates = Dates { errors: None };
+    dates.visit_root(root);
+    dates.errors.map_or(Ok(()), Err)
+}
+
+struct Dates {
+    errors: Option<syn::Error>,
+}
+
+impl Dates {
+    fn check(&mut self, lit: &syn::LitStr) {
+        let Err(spec) = check_format(&lit.value()) else {
+            return;
+        };
+        let e = err!(lit, format!("unsupported date format specifier `{spec}`"));
+        match &mut self.errors {
+            Some(errors) => errors.combine(e),
+            None => self.errors = Some(e),
+        }
+    }
+}
+
+impl<'ast, 'i> Visit<'ast, 'i> for Dates {
+    fn visit_item_for(&mut self, node: &'ast ItemFor<'i>) {
+        SynVisit::visit_expr(self, &node.expr);
+        visit::visit_item_for(self, node);
+    }
+
+    fn visit_item_if(&mut self, node: &'ast ItemIf<'i>) {
+        SynVisit::visit_expr(self, &node.cond);
+        visit::visit_item_if(self, node);
+    }
+
+    fn visit_if_branch(&mut self, node: &'ast IfBranch<'i>) {
+        if let IfBranch::ElseIf { cond, .. } = node {
+            SynVisit::visit_expr(self, cond);
+        }
+        visit::visit_if_branch(self, node);
+    }
+
+    fn visit_item_match(&mut self, node: &'ast ItemMatch<'i>) {
+        SynVisit::visit_expr(self, &node.expr);
+        visit::visit_item_match(self, node);
+    }
+
+    fn visit_match_arm(&mut self, node: &'ast MatchArm<'i>) {
+        if let Some(guard) = &node.guard {
+            SynVisit::visit_expr(self, guard);
+        }
+        visit::visit_match_arm(self, node);
+    }
+
+    fn visit_expr(&mut self, node: &'ast Expr<'i>) {
+        match node {
+            Expr::Include { args, .. } | Expr::Translate { args, .. } => {
+                for arg in args {
+                    self.visit_field_value(arg);
+                }
+            }
+            Expr::MacroCall { args, .. } => {
+                for arg in args {
+                    SynVisit::visit_expr(self, arg);
+                }
+            }
+            Expr::Stmt(stmt) => self.visit_stmt(stmt),
+            Expr::Expr(expr) => SynVisit::visit_expr(self, expr),
+            Expr::Extends(_) | Expr::SuperCall => {}
+        }
+    }
+}
+
+impl<'ast> SynVisit<'ast> for Dates {
+    fn visit_expr_method_call(&mut self, node: &'ast syn::ExprMethodCall) {
+        syn::visit::visit_expr_method_call(self, node);
+        let format = match (node.method.to_string().as_str(), node.args.len()) {
+            ("strftime", 1) => &node.args[0],
+            ("strftime_in", 2) => &node.args[1],
+            _ => return,
+        };
+        if let syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(lit),
+            ..
+        }) = format
+        {
+            self.check(lit);
+        }
+    }
+}
+
+/// Check a strftime style format against the specifiers `DateTimeExt` supports, this has
+/// to match the specifiers written by the stilts crate
+fn check_format(fmt: &str) -> Result<(), String> {
+    let mut chars = fmt.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            continue;
+        }
+        let mut spec = String::from('%');
+        let mut next = || {
+            let c = chars.next();
+            spec.extend(c);
+            c
+        };
+        let c = match next() {
+            Some('-') => next(),
+            c => c,
+        };
+        let supported = match c {
+            Some(
+                'Y' | 'y' | 'm' | 'd' | 'e' | 'H' | 'I' | 'M' | 'S' | 'f' | 'j' | 'p' | 'b' | 'h'
+                | 'B' | 'a' | 'A' | 'u' | 'w' | 'z' | 's' | 'F' | 'T' | 'R' | 'D' | 'n' | 't' | '%',
+            ) => true,
+            Some(':') => next() == Some('z'),
+            _ => false,
+        };
+        if !supported {
+            return Err(spec);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{check, check_format};
+
+    #[test]
+    fn formats() {
+        assert_eq!(check_format("%Y-%m-%d %-H:%M %:z %%"), Ok(()));
+        assert_eq!(check_format("%Q"), Err("%Q".to_string()));
+        assert_eq!(check_format("%-"), Err("%-".to_string()));
+        assert_eq!(check_format("%:y"), Err("%:y".to_string()));
+        assert_eq!(check_format("100%"), Err("%".to_string()));
+    }
+
+    #[test]
+    fn literal_formats_in_templates() {
+        let check_template = |source| {
+            let root = stilts_lang::parse_template(source, Default::default()).unwrap();
+            check(&root).map_err(|e| e.to_string())
+        };
+
+        assert_eq!(check_template("{% day.strftime(\"%F\") %}{% day.strftime(fmt) %}"), Ok(()));
+        assert_eq!(
+            check_template("{% for d in days %}{% call row(d.strftime_in(zone, \"%F %Q\")) %}{% end %}"),
+            Err("unsupported date format specifier `%Q`".to_string())
+        );
+        assert_eq!(
+            check_template("{% if x.strftime(\"%k\").to_string().is_empty() %}{% end %}"),
+            Err("unsupported date format specifier `%k`".to_string())
+        );
+    }
+}
@@ -0,0 +1,1165 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use cargo_metadata::camino::Utf8PathBuf;
+use proc_macro2::TokenStream;
+use quote::quote;
+use stilts_lang::parse_template;
+use stilts_lang::types::{
+    Expr, IfBranch, Item, ItemBlock, ItemFor, ItemIf, ItemMacro, ItemMatch, MatchArm, Root,
+};
+use stilts_lang::visit::{self, Visit};
+
+use crate::config::Config;
+use crate::context::{Escape, HtmlContext};
+use crate::err;
+use crate::parse::{TemplateAttrs, TemplateInput, TemplateSource};
+
+fn format_err(e: stilts_lang::Error) -> syn::Error {
+    #[cfg(not(any(feature = "narratable", feature = "fancy")))]
+    return err!(e.display_simple());
+
+    #[allow(unused_variables)]
+    #[cfg(feature = "narratable")]
+    let handler = miette::NarratableReportHandler::new();
+    #[cfg(feature = "fancy")]
+    let handler =
+        miette::GraphicalReportHandler::new_themed(miette::GraphicalTheme::unicode_nocolor());
+    #[cfg(any(feature = "narratable", feature = "fancy"))]
+    {
+        let mut s = String::new();
+        let s = handler
+            .render_report(&mut s, &e)
+            .map(|_| s)
+            .unwrap_or(e.display_simple());
+        err!(s)
+    }
+}
+
+#[derive(Debug)]
+pub struct Graph(Vec<TemplateNode>);
+
+impl Graph {
+    pub fn load(cfg: &Config, attrs: &TemplateAttrs) -> syn::Result<Self> {
+        let mut graph = Vec::with_capacity(1);
+
+        let mut node = Self::load_node(cfg, &attrs.source, attrs.escape.clone(), attrs.trim)?;
+        let mut parent = Self::get_parent(&node.root);
+
+        // isolate a single block
+        if let Some(block) = &attrs.block {
+            let Some(block) = node.blocks.remove(block) else {
+                return Err(err!(format!("isolated block not found: {block}")));
+            };
+
+            let blocks = Self::get_blocks(block.content.iter());
+            node.blocks = blocks;
+            node.root.content = block.content;
+
+            // blocks don't support inheritance
+            parent = None;
+        }
+
+        graph.push(node);
+
+        fn check_dependency_cycle(
+            cfg: &Config,
+            graph: &[TemplateNode],
+            path: &Utf8PathBuf,
+        ) -> Result<(), syn::Error> {
+            // check for graph cycle
+            if graph.iter().any(|t| t.data.path.as_ref() == Some(path)) {
+                let mut cycle_str = graph.iter().filter_map(|t| t.data.path.as_ref()).fold(
+                    String::new(),
+                    |mut acc, p| {
+                        acc.push_str(&format!("{} -> ", relative_to(cfg, p)));
+                        acc
+                    },
+                );
+                cycle_str.push_str(&relative_to(cfg, path));
+                Err(err!(format!("dependency cycle detected: {cycle_str}")))
+            } else {
+                Ok(())
+            }
+        }
+
+        while let Some(p) = parent {
+            let source = TemplateSource::new_file(p);
+            let node = Self::load_node(cfg, &source, attrs.escape.clone(), attrs.trim)?;
+
+            // always true, but this piggybacks on the transformation from `p` to `path`
+            if let Some(path) = &node.data.path {
+                check_dependency_cycle(cfg, &graph, path)?;
+            }
+
+            parent = Self::get_parent(&node.root);
+            graph.push(node);
+        }
+
+        graph.reverse();
+        Ok(Self(graph))
+    }
+
+    /// Read and parse a single template file or literal.
+    fn load_node(
+        cfg: &Config,
+        source: &TemplateSource,
+        escape_override: Option<syn::Path>,
+        trim_override: Option<bool>,
+    ) -> syn::Result<TemplateNode> {
+        let data = read_template(cfg, source)?;
+        let root = parse_template(&data.content, cfg.delimiters.clone())
+            .map(Root::into_owned)
+            .map_err(format_err)?;
+        #[cfg(feature = "datetime")]
+        crate::datetime::check(&root)?;
+
+        let blocks = Self::get_blocks(root.content.iter());
+
+        Ok(TemplateNode {
+            data,
+            blocks,
+            root,
+            escape_override,
+            trim_override,
+        })
+    }
+
+    // descend the template inheritance list rendering all of them sequentially
+    // while performing necessary expansions
+    pub fn expand(self, cfg: &Config, ctx: &mut HtmlContext) -> syn::Result<TokenStream> {
+        self.expand_with(cfg, ctx, &Macros::default())
+    }
+
+    // expand with the macros of the templates this one is included in
+    fn expand_with(
+        &self,
+        cfg: &Config,
+        ctx: &mut HtmlContext,
+        outer: &Macros,
+    ) -> syn::Result<TokenStream> {
+        let macros = outer.with(&self.0);
+        let mut expanded_blocks = Vec::new();
+        let mut toks = TokenStream::new();
+        let mut cur = Some(TemplateRef(0, &self.0, &macros));
+        while let Some(t) = cur {
+            if let Some(path) = t.data.path.as_ref().map(|p| p.as_str()) {
+                // this should ensure that the compiler knows that the code is dependent on this file
+                toks.extend(quote! { ::core::include_bytes!(#path); });
+            }
+            toks.extend(t.expand(cfg, &expanded_blocks, ctx)?);
+            expanded_blocks.extend(t.blocks.keys().map(|s| s.clone().into()));
+            cur = t.child();
+        }
+        Ok(toks)
+    }
+
+    // a lower bound on the length of the rendered output, this mirrors `expand`
+    // but only counts the content which will always be rendered
+    pub fn size_hint(&self, cfg: &Config) -> syn::Result<usize> {
+        let mut expanded_blocks = Vec::new();
+        let mut size = 0;
+        let macros = Macros::default();
+        let mut cur = Some(TemplateRef(0, &self.0, &macros));
+        while let Some(t) = cur {
+            size += t.size_hint(cfg, &expanded_blocks)?;
+            expanded_blocks.extend(t.blocks.keys().map(|s| s.clone().into()));
+            cur = t.child();
+        }
+        Ok(size)
+    }
+
+    fn get_parent<'a>(root: &Root<'a>) -> Option<std::borrow::Cow<'a, str>> {
+        match root.content.first() {
+            Some(Item::Expr(Expr::Extends(e))) => Some(e.clone()),
+            _ => None,
+        }
+    }
+
+    // the escaper of every template in the graph and the templates they include, if
+    // they all use the same one
+    pub fn escaper(&self, cfg: &Config) -> syn::Result<Option<syn::Path>> {
+        struct Includes<'a>(Vec<&'a str>);
+
+        impl<'a> Visit<'a, 'static> for Includes<'a> {
+            fn visit_expr(&mut self, expr: &'a Expr<'static>) {
+                if let Expr::Include { reference, .. } = expr {
+                    self.0.push(reference);
+                }
+            }
+        }
+
+        let mut escaper: Option<syn::Path> = None;
+        for node in &self.0 {
+            let node_escaper = node
+                .escape_override
+                .clone()
+                .unwrap_or_else(|| cfg.escaper(node.data.path.as_deref()));
+            let mut includes = Includes(Vec::new());
+            includes.visit_root(&node.root);
+            let mut escapers = vec![Some(node_escaper)];
+            for reference in includes.0 {
+                let attrs = TemplateAttrs {
+                    source: TemplateSource::new_file(reference),
+                    escape: node.escape_override.clone(),
+                    trim: node.trim_override,
+                    block: None,
+                    stream: None,
+                    locales: Vec::new(),
+                };
+                escapers.push(Graph::load(cfg, &attrs)?.escaper(cfg)?);
+            }
+            for other in escapers {
+                let Some(other) = other else {
+                    return Ok(None);
+                };
+                match &escaper {
+                    Some(escaper) if quote!(#escaper).to_string() != quote!(#other).to_string() => {
+                        return Ok(None)
+                    }
+                    _ => escaper = Some(other),
+                }
+            }
+        }
+        Ok(escaper)
+    }
+
+    // every block in some content and the blocks directly inside of them, blocks
+    // nested in other items like an `if` or a `for` are not collected
+    fn get_blocks<'a>(
+        content: impl IntoIterator<Item = &'a Item<'static>>,
+    ) -> HashMap<String, ItemBlock<'static>> {
+        struct Blocks(HashMap<String, ItemBlock<'static>>);
+
+        impl<'a> Visit<'a, 'static> for Blocks {
+            fn visit_item(&mut self, item: &'a Item<'static>) {
+                if let Item::Block(block) = item {
+                    self.visit_item_block(block);
+                }
+            }
+
+            fn visit_item_block(&mut self, block: &'a ItemBlock<'static>) {
+                // nested blocks first so an outer block wins over one with the same name inside it
+                visit::visit_item_block(self, block);
+                self.0.insert(block.name.to_string(), block.clone());
+            }
+        }
+
+        let mut blocks = Blocks(HashMap::new());
+        content.into_iter().for_each(|item| blocks.visit_item(item));
+        blocks.0
+    }
+}
+
+// a macro definition and the template it is defined in
+#[derive(Clone, Copy)]
+struct MacroDef<'a> {
+    template: usize,
+    graph: &'a [TemplateNode],
+    item: &'a ItemMacro<'static>,
+}
+
+// the macros that can be called from a template, a macro body is expanded again for
+// every HTML context it is called from so its expressions are escaped for that context
+#[derive(Default)]
+struct Macros<'a> {
+    defs: HashMap<String, MacroDef<'a>>,
+    // the macros being expanded with the context they were called from, and whether they
+    // have called themselves
+    expanding: Rc<RefCell<Vec<(String, HtmlContext, bool)>>>,
+}
+
+impl<'a> Macros<'a> {
+    // these macros along with the ones defined in a graph of templates
+    fn with(&self, graph: &'a [TemplateNode]) -> Self {
+        struct Collect<'a, 'm> {
+            template: usize,
+            graph: &'a [TemplateNode],
+            defs: &'m mut HashMap<String, MacroDef<'a>>,
+        }
+
+        impl<'a> Visit<'a, 'static> for Collect<'a, '_> {
+            fn visit_item_macro(&mut self, item: &'a ItemMacro<'static>) {
+                visit::visit_item_macro(self, item);
+                let def = MacroDef {
+                    template: self.template,
+                    graph: self.graph,
+                    item,
+                };
+                self.defs.insert(item.name.to_string(), def);
+            }
+        }
+
+        let mut defs = self.defs.clone();
+        for (template, node) in graph.iter().enumerate() {
+            let mut collect = Collect {
+                template,
+                graph,
+                defs: &mut defs,
+            };
+            collect.visit_root(&node.root);
+        }
+        Self {
+            defs,
+            expanding: Rc::clone(&self.expanding),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct TemplateRef<'a>(usize, &'a [TemplateNode], &'a Macros<'a>);
+
+impl<'a> TemplateRef<'a> {
+    // get the parent node if one exists
+    fn parent(self) -> Option<Self> {
+        self.0.checked_sub(1).map(|idx| Self(idx, self.1, self.2))
+    }
+
+    // get the child node if one exists
+    fn child(self) -> Option<Self> {
+        let idx = self.0 + 1;
+        if idx < self.1.len() {
+            Some(Self(idx, self.1, self.2))
+        } else {
+            None
+        }
+    }
+
+    // get the deepest child that contains the specified block_name
+    // checks ALL nodes reguardless of if the direct parent did not contain
+    // the block, returns the current template if none is found
+    fn deepest_child(self, block_name: impl AsRef<str>) -> Self {
+        let mut cur = self;
+        let mut next = self.child();
+        while let Some(t) = next {
+            if t.blocks.contains_key(block_name.as_ref()) {
+                cur = t;
+            }
+            next = next.unwrap().child();
+        }
+        cur
+    }
+
+    // expand a block directly (does not lookup the deepest child to expand)
+    fn expand_block_inner(
+        self,
+        cfg: &Config,
+        block: &ItemBlock,
+        ctx: &mut HtmlContext,
+    ) -> syn::Result<TokenStream> {
+        block
+            .content
+            .iter()
+            .map(|bi| match bi {
+                Item::Expr(Expr::SuperCall) => {
+                    let parent = self.parent();
+                    let pblock = parent
+                        .as_ref()
+                        .and_then(|p| p.blocks.get(block.name.as_ref()));
+                    if let Some((parent, pblock)) = parent.zip(pblock) {
+                        parent.expand_block_inner(cfg, pblock, ctx)
+                    } else {
+                        Ok(quote! {})
+                    }
+                }
+                item => self.expand_item(cfg, item, ctx),
+            })
+            .collect()
+    }
+
+    // expand a block by navigating to the deepest child and
+    // then using `expand_block_inner`
+    fn expand_block(
+        self,
+        cfg: &Config,
+        block: &ItemBlock,
+        ctx: &mut HtmlContext,
+    ) -> syn::Result<TokenStream> {
+        let deepest = self.deepest_child(&block.name);
+        let block = deepest.blocks.get(block.name.as_ref()).unwrap();
+        deepest.expand_block_inner(cfg, block, ctx)
+    }
+
+    // the size hint of a block directly, mirrors `expand_block_inner`
+    fn size_hint_block_inner(self, cfg: &Config, block: &ItemBlock) -> syn::Result<usize> {
+        block
+            .content
+            .iter()
+            .map(|bi| match bi {
+                Item::Expr(Expr::SuperCall) => {
+                    let parent = self.parent();
+                    let pblock = parent
+                        .as_ref()
+                        .and_then(|p| p.blocks.get(block.name.as_ref()));
+                    if let Some((parent, pblock)) = parent.zip(pblock) {
+                        parent.size_hint_block_inner(cfg, pblock)
+                    } else {
+                        Ok(0)
+                    }
+                }
+                item => self.size_hint_item(cfg, item),
+            })
+            .sum()
+    }
+
+    // the size hint of a block from the deepest child, mirrors `expand_block`
+    fn size_hint_block(self, cfg: &Config, block: &ItemBlock) -> syn::Result<usize> {
+        let deepest = self.deepest_child(&block.name);
+        let block = deepest.blocks.get(block.name.as_ref()).unwrap();
+        deepest.size_hint_block_inner(cfg, block)
+    }
+
+    // expand the content of one branch of a conditional starting from the context
+    // before the conditional, then join its final context with the other branches
+    fn expand_branch(
+        self,
+        cfg: &Config,
+        content: &[Item],
+        start: &HtmlContext,
+        end: &mut Option<HtmlContext>,
+    ) -> syn::Result<TokenStream> {
+        let mut ctx = start.clone();
+        let items = content
+            .iter()
+            .map(|i| self.expand_item(cfg, i, &mut ctx))
+            .collect::<Result<_, _>>()?;
+        join_context(end, ctx)?;
+        Ok(items)
+    }
+
+    // expand the closing statement of an if expression
+    fn expand_if_branch(
+        self,
+        cfg: &Config,
+        close: &IfBranch,
+        start: &HtmlContext,
+        end: &mut Option<HtmlContext>,
+    ) -> syn::Result<TokenStream> {
+        match close {
+            IfBranch::End => {
+                // not taking any branch leaves the context unchanged
+                join_context(end, start.clone())?;
+                Ok(quote! {})
+            }
+            IfBranch::Else { content, .. } => {
+                let items = self.expand_branch(cfg, content, start, end)?;
+                Ok(quote! { else { #items } })
+            }
+            IfBranch::ElseIf {
+                cond,
+                content,
+                branch,
+            } => {
+                let items = self.expand_branch(cfg, content, start, end)?;
+                let close = self.expand_if_branch(cfg, branch, start, end)?;
+                Ok(quote! {
+                    else if #cond {
+                        #items
+                    } #close
+                })
+            }
+        }
+    }
+
+    // expand an arm of a match expression
+    fn expand_match_arm(
+        self,
+        cfg: &Config,
+        arm: &MatchArm,
+        start: &HtmlContext,
+        end: &mut Option<HtmlContext>,
+    ) -> syn::Result<TokenStream> {
+        let pat = &arm.pat;
+        let guard = arm.guard.as_ref().map(|g| quote! { if #g });
+        let items = self.expand_branch(cfg, &arm.content, start, end)?;
+        Ok(quote! { #pat #guard => { #items } })
+    }
+
+    // apply trimming to template content, returns none if nothing should be written
+    fn content<'c>(self, cfg: &Config, c: &'c str) -> Option<&'c str> {
+        let c = match self.trim_override.unwrap_or(cfg.trim) {
+            true => c.trim(),
+            false => c,
+        };
+        if !c.is_empty() && !c.chars().all(char::is_whitespace) {
+            Some(c)
+        } else {
+            None
+        }
+    }
+
+    // the number of bytes an item will always write, anything that is conditional
+    // or repeated (loops, ifs, matches, and macro calls) is not counted
+    fn size_hint_item(self, cfg: &Config, item: &Item) -> syn::Result<usize> {
+        match item {
+            Item::Content(c) => Ok(self.content(cfg, c).map_or(0, str::len)),
+            Item::Block(block_item) => self.size_hint_block(cfg, block_item),
+            Item::Expr(Expr::Include { reference, .. }) => {
+                let attrs = TemplateAttrs {
+                    source: TemplateSource::new_file(reference),
+                    escape: self.escape_override.clone(),
+                    trim: self.trim_override,
+                    block: None,
+                    stream: None,
+                    locales: Vec::new(),
+                };
+                Graph::load(cfg, &attrs)?.size_hint(cfg)
+            }
+            _ => Ok(0),
+        }
+    }
+
+    // the escaper used for expressions in this template
+    fn escaper(self, cfg: &Config) -> syn::Path {
+        self.escape_override
+            .clone()
+            .unwrap_or_else(|| cfg.escaper(self.data.path.as_deref()))
+    }
+
+    // whether expressions are escaped depending on where they are in the html, this is
+    // the case when the escaper resolves to the html escaper of stilts however it is written
+    fn contextual(self, cfg: &Config) -> bool {
+        let escaper = self.escaper(cfg);
+        cfg.contextual_escape
+            && escaper.segments.len() == 3
+            && escaper
+                .segments
+                .iter()
+                .zip(["stilts", "escaping", "Html"])
+                .all(|(segment, name)| segment.ident == name && segment.arguments.is_none())
+    }
+
+    // expand an item which may be one of many different things
+    fn expand_item(
+        self,
+        cfg: &Config,
+        item: &Item,
+        ctx: &mut HtmlContext,
+    ) -> syn::Result<TokenStream> {
+        let writer = &cfg.writer_name;
+        match item {
+            Item::Content(c) => match self.content(cfg, c) {
+                Some(c) => {
+                    if self.contextual(cfg) {
+                        ctx.advance(c).map_err(|e| err!(e))?;
+                    }
+                    #[cfg(feature = "async")]
+                    let flush = crate::async_io::flush_point();
+                    #[cfg(not(feature = "async"))]
+                    let flush = quote! {};
+                    Ok(quote! { #writer.write_str(#c)?; #flush })
+                }
+                None => Ok(quote! {}),
+            },
+            Item::Block(block_item) => self.expand_block(cfg, block_item, ctx),
+            Item::For(ItemFor {
+                label,
+                pat,
+                expr,
+                content,
+            }) => {
+                let expand_body = |ctx: &mut HtmlContext| {
+                    content
+                        .iter()
+                        .map(|i| self.expand_item(cfg, i, ctx))
+                        .collect::<syn::Result<TokenStream>>()
+                };
+                let start = ctx.clone();
+                let mut content = expand_body(ctx)?;
+                if *ctx != start {
+                    // the next iteration starts where the last one ended, so expand
+                    // the body again from a context that covers both
+                    let joined = start.join(ctx).ok_or_else(|| context_err(&start, ctx))?;
+                    *ctx = joined.clone();
+                    content = expand_body(ctx)?;
+                    if joined.join(ctx).as_ref() != Some(&joined) {
+                        return Err(context_err(&joined, ctx));
+                    }
+                    *ctx = joined;
+                }
+                Ok(quote! {
+                    #label for #pat in #expr {
+                        #content
+                    }
+                })
+            }
+            Item::If(ItemIf {
+                cond,
+                content,
+                branch,
+            }) => {
+                let start = ctx.clone();
+                let mut end = None;
+                let items = self.expand_branch(cfg, content, &start, &mut end)?;
+                let branch = self.expand_if_branch(cfg, branch, &start, &mut end)?;
+                *ctx = end.unwrap_or(start);
+                Ok(quote! {
+                    if #cond {
+                        #items
+                    } #branch
+                })
+            }
+            Item::Match(ItemMatch { expr, arms }) => {
+                let start = ctx.clone();
+                let mut end = None;
+                let arms: TokenStream = arms
+                    .iter()
+                    .map(|i| self.expand_match_arm(cfg, i, &start, &mut end))
+                    .collect::<Result<_, _>>()?;
+                *ctx = end.unwrap_or(start);
+                Ok(quote! {
+                    match #expr {
+                        #arms
+                    }
+                })
+            }
+            Item::Macro(item) => {
+                // calls from text use this definition, calls from anywhere else expand
+                // the body again where they are
+                let mut ctx = HtmlContext::default();
+                let content = item
+                    .content
+                    .iter()
+                    .map(|i| self.expand_item(cfg, i, &mut ctx))
+                    .collect::<Result<TokenStream, _>>()?;
+                Ok(macro_fn(cfg, item, content))
+            }
+            Item::Expr(Expr::Extends(_)) => Ok(quote! {}),
+            Item::Expr(Expr::SuperCall) => Ok(quote! {}),
+            Item::Expr(Expr::Include { reference, args }) => {
+                let attrs = TemplateAttrs {
+                    source: TemplateSource::new_file(reference),
+                    escape: self.escape_override.clone(),
+                    trim: self.trim_override,
+                    block: None,
+                    stream: None,
+                    locales: Vec::new(),
+                };
+                let graph = Graph::load(cfg, &attrs)?;
+                let included = graph.expand_with(cfg, ctx, self.2)?;
+                let arg_assignments = args.into_iter().map(|arg| {
+                    let syn::FieldValue { member, expr, .. } = arg;
+                    quote! {let #member = #expr;}
+                });
+                Ok(quote! {
+                    {
+                        #(#arg_assignments)*
+                        #included
+                    }
+                })
+            }
+            Item::Expr(Expr::MacroCall { name, args }) => {
+                let writer = &cfg.writer_name;
+                // macros translate into the locale of the template which calls them
+                #[cfg(feature = "i18n")]
+                let call = quote! {
+                    #name(#writer, __stilts_locale, #args)?;
+                };
+                #[cfg(not(feature = "i18n"))]
+                let call = quote! {
+                    #name(#writer, #args)?;
+                };
+                let def = self.2.defs.get(&name.to_string());
+                match def {
+                    Some(def) if self.contextual(cfg) => {
+                        self.expand_macro_call(cfg, def, call, ctx)
+                    }
+                    _ => Ok(call),
+                }
+            }
+            #[cfg(feature = "i18n")]
+            Item::Expr(Expr::Translate { id, args }) => {
+                let message = crate::i18n::expand_translate(cfg, id, args)?;
+                Ok(self.expand_value(cfg, message, ctx))
+            }
+            #[cfg(not(feature = "i18n"))]
+            Item::Expr(Expr::Translate { .. }) => Err(err!(
+                "translations require the `i18n` feature of stilts to be enabled"
+            )),
+            Item::Expr(Expr::Expr(expr)) => Ok(self.expand_value(cfg, quote! { #expr }, ctx)),
+            Item::Expr(Expr::Stmt(stmt)) => Ok(quote! { #stmt }),
+        }
+    }
+
+    // expand a call to a macro for the context it is called from
+    fn expand_macro_call(
+        self,
+        cfg: &Config,
+        def: &MacroDef,
+        call: TokenStream,
+        ctx: &mut HtmlContext,
+    ) -> syn::Result<TokenStream> {
+        let name = def.item.name.to_string();
+        let recursive = self
+            .2
+            .expanding
+            .borrow_mut()
+            .iter_mut()
+            .find(|(n, ..)| *n == name)
+            .map(|(_, start, called)| {
+                *called = true;
+                start.clone()
+            });
+        if let Some(start) = recursive {
+            // the body is checked to end where it started once it is expanded
+            return match start == *ctx {
+                true => Ok(call),
+                false => Err(err!(
+                    def.item.name,
+                    format!(
+                        "macro `{name}` is called from {} inside of itself but it was called from {}",
+                        ctx.describe(),
+                        start.describe()
+                    )
+                )),
+            };
+        }
+
+        let start = ctx.clone();
+        let template = TemplateRef(def.template, def.graph, self.2);
+        self.2
+            .expanding
+            .borrow_mut()
+            .push((name.clone(), start.clone(), false));
+        let content = def
+            .item
+            .content
+            .iter()
+            .map(|i| template.expand_item(cfg, i, ctx))
+            .collect::<syn::Result<TokenStream>>();
+        let (_, _, called) = self.2.expanding.borrow_mut().pop().unwrap();
+        let content = content?;
+        if called && *ctx != start {
+            return Err(err!(
+                def.item.name,
+                format!(
+                    "recursive macro `{name}` must end in the HTML context it starts in, it starts in {} and ends in {}",
+                    start.describe(),
+                    ctx.describe()
+                )
+            ));
+        }
+
+        if start == HtmlContext::default() {
+            // the definition of the macro is expanded from text
+            return Ok(call);
+        }
+        let item = macro_fn(cfg, def.item, content);
+        Ok(quote! {
+            {
+                #item
+                #call
+            }
+        })
+    }
+
+    // write a value escaped for the context it appears in
+    fn expand_value(self, cfg: &Config, expr: TokenStream, ctx: &mut HtmlContext) -> TokenStream {
+        let writer = &cfg.writer_name;
+        let escaper = self.escaper(cfg);
+        let escape = match self.contextual(cfg) {
+            true => ctx.escape(),
+            false => Escape::Default,
+        };
+        ctx.after_expr();
+        let value = match escape {
+            Escape::Default => {
+                quote! { (&::stilts::escaping::Probe(&#expr, ::core::marker::PhantomData::<#escaper>)).escape(#escaper) }
+            }
+            Escape::With(name) => {
+                let escaper = quote::format_ident!("{name}");
+                quote! { (&::stilts::escaping::Probe(&#expr, ::core::marker::PhantomData::<::stilts::escaping::#escaper>)).escape(::stilts::escaping::#escaper) }
+            }
+            Escape::RequireSafe => quote! { ::stilts::escaping::require_safe(&#expr) },
+            Escape::RequireSafeIn(name) => {
+                let part = quote::format_ident!("{name}");
+                quote! { ::stilts::escaping::require_safe_in::<::stilts::escaping::#part, _>(&#expr) }
+            }
+        };
+        quote! { ::core::write!(#writer, "{}", #value)?; }
+    }
+
+    // the size hint of the whole template, mirrors `expand`
+    fn size_hint(self, cfg: &Config, prev: &[std::borrow::Cow<'_, str>]) -> syn::Result<usize> {
+        self.root
+            .content
+            .iter()
+            .filter(|i| match i {
+                Item::Block(ItemBlock { name, .. }) => !prev.contains(name),
+                _ => true,
+            })
+            .map(|i| self.size_hint_item(cfg, i))
+            .sum()
+    }
+
+    // expand the whole template
+    fn expand(
+        self,
+        cfg: &Config,
+        prev: &[std::borrow::Cow<'_, str>],
+        ctx: &mut HtmlContext,
+    ) -> syn::Result<TokenStream> {
+        self.root
+            .content
+            .iter()
+            .filter(|i| match i {
+                Item::Block(ItemBlock { name, .. }) => !prev.contains(name),
+                _ => true,
+            })
+            .map(|i| self.expand_item(cfg, i, ctx))
+            .collect()
+    }
+}
+
+impl<'a> std::ops::Deref for TemplateRef<'a> {
+    type Target = TemplateNode;
+
+    fn deref(&self) -> &Self::Target {
+        &self.1[self.0]
+    }
+}
+
+#[derive(Debug)]
+struct TemplateNode {
+    data: TemplateData,
+    escape_override: Option<syn::Path>,
+    trim_override: Option<bool>,
+    blocks: HashMap<String, ItemBlock<'static>>,
+    root: Root<'static>,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct TemplateData {
+    path: Option<Utf8PathBuf>,
+    content: String,
+}
+
+// the function that a macro is expanded into
+fn macro_fn(cfg: &Config, item: &ItemMacro, content: TokenStream) -> TokenStream {
+    let ItemMacro { name, args, .. } = item;
+    let writer = &cfg.writer_name;
+    let writer_ty = quote! { &mut (impl ::core::fmt::Write + ?::core::marker::Sized) };
+    // macros are always synchronous so they can not flush
+    #[cfg(feature = "async")]
+    let flush = crate::async_io::flush_noop();
+    #[cfg(not(feature = "async"))]
+    let flush = quote! {};
+    // the locale of the template is passed along to every call
+    #[cfg(feature = "i18n")]
+    let locale = quote! { __stilts_locale: ::core::option::Option<&str>, };
+    #[cfg(not(feature = "i18n"))]
+    let locale = quote! {};
+    quote! {
+        fn #name(#writer: #writer_ty, #locale #args) -> ::core::fmt::Result {
+            #flush
+            #content
+            Ok(())
+        };
+    }
+}
+
+// join the context at the end of a branch into the context after the whole conditional
+fn join_context(end: &mut Option<HtmlContext>, ctx: HtmlContext) -> syn::Result<()> {
+    *end = match end.take() {
+        Some(end) => Some(end.join(&ctx).ok_or_else(|| context_err(&end, &ctx))?),
+        None => Some(ctx),
+    };
+    Ok(())
+}
+
+fn context_err(a: &HtmlContext, b: &HtmlContext) -> syn::Error {
+    err!(format!(
+        "branches of the template end in different HTML contexts, {} and {}",
+        a.describe(),
+        b.describe()
+    ))
+}
+
+fn relative_to(config: &Config, path: &Utf8PathBuf) -> String {
+    path.as_str().replace(config.template_dir.as_str(), "")
+}
+
+fn read_template(config: &Config, source: &TemplateSource) -> syn::Result<TemplateData> {
+    match source {
+        TemplateSource::File(path) => {
+            let path = config.template_dir.join(path.value());
+            Ok(TemplateData {
+                content: std::fs::read_to_string(&path)
+                    .map_err(|io| err!(format!("{io} while reading {}", path.clone())))?,
+                path: Some(path),
+            })
+        }
+        TemplateSource::Literal(content) => Ok(TemplateData {
+            path: None,
+            content: content.value(),
+        }),
+    }
+}
+
+pub fn template(input: TemplateInput) -> syn::Result<TokenStream> {
+    let TemplateInput {
+        ident,
+        generics,
+        fields,
+        attrs,
+    } = &input;
+
+    let config = Config::load().map_err(|e| err!(format!("Stilts Config Error: {e}")))?;
+
+    let mime_type = attrs.source.mime_type(&config).map(|m| m.to_string());
+    let mime_type = match mime_type {
+        Some(mt) => quote! { Some(#mt) },
+        None => quote! { None },
+    };
+
+    let graph = Graph::load(&config, attrs)?;
+    let static_size = graph.size_hint(&config)?;
+    // nested templates are trusted by templates with the same or a less strict escaper,
+    // which is only known if every part of the template uses the same one
+    let mut escaper = graph.escaper(&config)?;
+    let template_code = graph.expand(&config, &mut HtmlContext::default())?;
+    // compile the variant of the template for each locale, a missing variant is an error
+    // since the template would not be rebuilt once it is added
+    let mut variant_locales = Vec::new();
+    let mut variant_code = Vec::new();
+    let mut variant_sizes = Vec::new();
+    for locale in &attrs.locales {
+        let Some(path) = attrs.source.localized(&locale.value()) else {
+            continue;
+        };
+        if !config.template_dir.join(&path).is_file() {
+            return Err(err!(
+                locale,
+                format!("the variant `{path}` for the locale does not exist, locales without a variant use the base template and should not be listed")
+            ));
+        }
+        let variant = TemplateAttrs {
+            source: TemplateSource::new_file(path),
+            escape: attrs.escape.clone(),
+            trim: attrs.trim,
+            block: attrs.block.clone(),
+            stream: None,
+            locales: Vec::new(),
+        };
+        let graph = Graph::load(&config, &variant)?;
+        let variant_escaper = graph.escaper(&config)?;
+        if quote!(#escaper).to_string() != quote!(#variant_escaper).to_string() {
+            escaper = None;
+        }
+        variant_sizes.push(graph.size_hint(&config)?);
+        variant_code.push(graph.expand(&config, &mut HtmlContext::default())?);
+        variant_locales.push(locale.value());
+    }
+    let field_size_hints = fields
+        .iter()
+        .filter_map(|f| {
+            let ident = &f.ident;
+            f.size_hint
+                .as_ref()
+                .map(|hint| quote! { + (#hint)(&self.#ident) })
+        })
+        .collect::<Vec<_>>();
+
+    let writer = &config.writer_name;
+    let field_idents = fields.iter().map(|f| &f.ident);
+    #[cfg(feature = "i18n")]
+    let locale = {
+        let mut locales = fields.iter().filter(|f| f.locale);
+        let locale = locales.next();
+        if let Some(extra) = locales.next() {
+            return Err(err!(extra.ident, "only one field can be the locale of a template"));
+        }
+        match locale.map(|f| &f.ident) {
+            Some(ident) => quote! {
+                let __stilts_locale = ::core::option::Option::Some(
+                    ::core::convert::AsRef::<str>::as_ref(#ident)
+                );
+            },
+            None => quote! { let __stilts_locale: ::core::option::Option<&str> = ::core::option::Option::None; },
+        }
+    };
+    #[cfg(not(feature = "i18n"))]
+    let locale = quote! {};
+    #[cfg(feature = "serde")]
+    let serialize_ext = quote! { use ::stilts::SerializeExt as _; };
+    #[cfg(not(feature = "serde"))]
+    let serialize_ext = quote! {};
+    #[cfg(feature = "datetime")]
+    let datetime_ext = quote! { use ::stilts::DateTimeExt as _; };
+    #[cfg(not(feature = "datetime"))]
+    let datetime_ext = quote! {};
+    let prelude = quote! {
+        #serialize_ext
+        #datetime_ext
+        use ::stilts::DisplayExt as _;
+        use ::stilts::DebugExt as _;
+        use ::stilts::IterExt as _;
+        use ::stilts::NumberExt as _;
+        use ::stilts::escaping::EscapeSafe as _;
+        use ::stilts::escaping::EscapeDisplay as _;
+        let Self {
+            #(#field_idents,)*
+            ..
+        } = self;
+        #locale
+    };
+
+    let (impl_gen, type_gen, where_clause) = generics.split_for_impl();
+
+    let escaper = escaper.map(|escaper| {
+        quote! {
+            impl #impl_gen ::stilts::escaping::TemplateEscaper for #ident #type_gen #where_clause {
+                type Escaper = #escaper;
+            }
+        }
+    });
+
+    #[cfg(feature = "async")]
+    let flush = crate::async_io::flush_noop();
+    #[cfg(not(feature = "async"))]
+    let flush = quote! {};
+    // templates with translations can also be rendered in a locale without variants
+    #[cfg(feature = "i18n")]
+    let translated = config.catalog.get().is_some();
+    #[cfg(not(feature = "i18n"))]
+    let translated = false;
+    let localized = if attrs.locales.is_empty() && !translated {
+        quote! {}
+    } else {
+        let indices = (0..variant_code.len()).collect::<Vec<_>>();
+        // translations inside of the variant use the requested locale
+        #[cfg(feature = "i18n")]
+        let locale = quote! { let __stilts_locale = ::core::option::Option::Some(__stilts_requested); };
+        #[cfg(not(feature = "i18n"))]
+        let locale = quote! {};
+        quote! {
+            impl #impl_gen #ident #type_gen #where_clause {
+                /// Render the template in a locale to a string
+                ///
+                /// Translations use the locale, and locales without a variant of the template
+                /// render the base template.
+                pub fn render_locale(&self, locale: &str) -> ::core::result::Result<::std::string::String, ::core::fmt::Error> {
+                    // sized from the variant which is rendered rather than the base template
+                    let size = match ::stilts::select_locale(locale, &[#(#variant_locales),*]) {
+                        #(::core::option::Option::Some(#indices) => #variant_sizes,)*
+                        _ => #static_size,
+                    } #(#field_size_hints)*;
+                    let mut out = ::std::string::String::with_capacity(size);
+                    self.fmt_locale(locale, &mut out)?;
+                    ::core::result::Result::Ok(out)
+                }
+
+                /// Write the template in a locale
+                ///
+                /// Translations use the locale, and locales without a variant of the template
+                /// write the base template.
+                pub fn fmt_locale(&self, locale: &str, #writer: &mut (impl ::core::fmt::Write + ?::core::marker::Sized)) -> ::core::fmt::Result {
+                    // bound before the fields of the template which may shadow `locale`
+                    let __stilts_requested = locale;
+                    let __stilts_variant = ::stilts::select_locale(__stilts_requested, &[#(#variant_locales),*]);
+                    #flush
+                    #prelude
+                    #locale
+                    match __stilts_variant {
+                        #(::core::option::Option::Some(#indices) => { #variant_code })*
+                        _ => { #template_code }
+                    }
+                    ::core::result::Result::Ok(())
+                }
+            }
+        }
+    };
+
+    #[allow(unused_mut)]
+    let mut integrations = TokenStream::new();
+    #[cfg(feature = "actix-web")]
+    integrations.extend(crate::integrations::actix_web::expand_integration(
+        ident,
+        &impl_gen,
+        &type_gen,
+        &where_clause,
+        attrs.stream.as_ref(),
+    ));
+    #[cfg(feature = "axum")]
+    integrations.extend(crate::integrations::axum::expand_integration(
+        ident,
+        &impl_gen,
+        &type_gen,
+        &where_clause,
+        attrs.stream.as_ref(),
+    ));
+    #[cfg(feature = "gotham")]
+    integrations.extend(crate::integrations::gotham::expand_integration(
+        ident,
+        &impl_gen,
+        &type_gen,
+        &where_clause,
+        attrs.stream.as_ref(),
+    ));
+    #[cfg(feature = "rocket")]
+    integrations.extend(crate::integrations::rocket::expand_integration(
+        ident,
+        generics,
+        attrs.stream.as_ref(),
+    ));
+    #[cfg(feature = "warp")]
+    integrations.extend(crate::integrations::warp::expand_integration(
+        ident,
+        &impl_gen,
+        &type_gen,
+        &where_clause,
+        attrs.stream.as_ref(),
+    ));
+
+    #[cfg(feature = "async")]
+    integrations.extend(crate::async_io::expand_async_template(
+        ident,
+        &impl_gen,
+        &type_gen,
+        &where_clause,
+        writer,
+        &prelude,
+        &template_code,
+    ));
+
+    Ok(quote! {
+        #integrations
+        #localized
+        #escaper
+        impl #impl_gen ::stilts::Template for #ident #type_gen #where_clause {
+            fn mime_str(&self) -> ::core::option::Option<&'static str> {
+                #mime_type
+            }
+
+            fn size_hint(&self) -> usize {
+                #static_size #(#field_size_hints)*
+            }
+
+            fn fmt(&self, #writer: &mut (impl ::core::fmt::Write + ?::core::marker::Sized)) -> ::core::fmt::Result {
+                #flush
+                #prelude
+
+                #template_code
+                Ok(())
+            }
+        }
+    })
+}
+
+pub fn default_template_impl(input: &TemplateInput) -> TokenStream {
+    let TemplateInput {
+        ident, generics, ..
+    } = input;
+
+    let (impl_gen, type_gen, where_clause) = generics.split_for_impl();
+    quote! {
+        impl #impl_gen ::stilts::Template for #ident #type_gen #where_clause {
+            fn mime_str(&self) -> ::core::option::Option<&'static str> { None }
+
+            fn fmt(&self, _w: &mut (impl ::core::fmt::Write + ?::core::marker::Sized)) -> ::core::fmt::Result {
+                ::core::unimplemented!("compilation error within template")
+            }
+        }
+    }
+}
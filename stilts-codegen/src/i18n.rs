@@ -0,0 +1,257 @@
+use std::collections::{BTreeSet, HashMap};
+
+use cargo_metadata::camino::{Utf8Path, Utf8PathBuf};
+use fluent_syntax::ast::{
+    CallArguments, Entry, Expression, InlineExpression, Pattern, PatternElement,
+};
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::config::Config;
+use crate::err;
+
+/// The messages of the default locale which translations are checked against
+pub struct Catalog {
+    /// Every `.ftl` file of every locale along with its locale, these are embedded
+    /// into templates which use translations
+    pub sources: Vec<(String, Utf8PathBuf)>,
+    locale: String,
+    messages: HashMap<String, Message>,
+}
+
+#[derive(Default)]
+struct Message {
+    value: Option<Uses>,
+    attributes: HashMap<String, Uses>,
+}
+
+// what a single pattern refers to, variables are shared with referenced messages
+#[derive(Default)]
+struct Uses {
+    variables: BTreeSet<String>,
+    messages: Vec<(String, Option<String>)>,
+}
+
+impl Uses {
+    fn of(pattern: &Pattern<&str>) -> Self {
+        let mut uses = Self::default();
+        uses.pattern(pattern);
+        uses
+    }
+
+    fn pattern(&mut self, pattern: &Pattern<&str>) {
+        for element in &pattern.elements {
+            if let PatternElement::Placeable { expression } = element {
+                self.expression(expression);
+            }
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression<&str>) {
+        match expression {
+            Expression::Select { selector, variants } => {
+                self.inline(selector);
+                for variant in variants {
+                    self.pattern(&variant.value);
+                }
+            }
+            Expression::Inline(inline) => self.inline(inline),
+        }
+    }
+
+    fn inline(&mut self, inline: &InlineExpression<&str>) {
+        match inline {
+            InlineExpression::VariableReference { id } => {
+                self.variables.insert(id.name.to_string());
+            }
+            InlineExpression::MessageReference { id, attribute } => {
+                let attribute = attribute.as_ref().map(|attr| attr.name.to_string());
+                self.messages.push((id.name.to_string(), attribute));
+            }
+            InlineExpression::FunctionReference { arguments, .. } => self.arguments(arguments),
+            InlineExpression::Placeable { expression } => self.expression(expression),
+            // terms get their own arguments and can not see the variables of the message
+            InlineExpression::TermReference { .. }
+            | InlineExpression::StringLiteral { .. }
+            | InlineExpression::NumberLiteral { .. } => {}
+        }
+    }
+
+    fn arguments(&mut self, arguments: &CallArguments<&str>) {
+        for arg in &arguments.positional {
+            self.inline(arg);
+        }
+        for arg in &arguments.named {
+            self.inline(&arg.value);
+        }
+    }
+}
+
+// the `.ftl` files directly inside of a directory
+fn ftl_files(dir: &Utf8Path) -> syn::Result<Vec<Utf8PathBuf>> {
+    let mut files = std::fs::read_dir(dir)
+        .map_err(|e| err!(format!("{e} while reading {dir}")))?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| Utf8PathBuf::from_path_buf(entry.path()).ok())
+        .filter(|path| path.extension() == Some("ftl"))
+        .collect::<Vec<_>>();
+    files.sort();
+    Ok(files)
+}
+
+impl Catalog {
+    pub fn load(cfg: &Config) -> syn::Result<Self> {
+        let files = ftl_files(&cfg.i18n_dir.join(&cfg.default_locale))?;
+
+        let mut locales = std::fs::read_dir(&cfg.i18n_dir)
+            .map_err(|e| err!(format!("{e} while reading {}", cfg.i18n_dir)))?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .collect::<Vec<_>>();
+        locales.sort();
+        let mut sources = Vec::new();
+        for locale in locales {
+            for file in ftl_files(&cfg.i18n_dir.join(&locale))? {
+                sources.push((locale.clone(), file));
+            }
+        }
+
+        let mut messages = HashMap::new();
+        for path in &files {
+            let source = std::fs::read_to_string(path)
+                .map_err(|e| err!(format!("{e} while reading {path}")))?;
+            let resource = fluent_syntax::parser::parse(source.as_str()).map_err(|(_, errors)| {
+                let errors = errors.iter().map(|e| e.kind.to_string()).collect::<Vec<_>>();
+                err!(format!("unable to parse {path}: {}", errors.join(", ")))
+            })?;
+            for entry in resource.body {
+                let Entry::Message(message) = entry else {
+                    continue;
+                };
+                let attributes = message
+                    .attributes
+                    .iter()
+                    .map(|attr| (attr.id.name.to_string(), Uses::of(&attr.value)))
+                    .collect();
+                messages.insert(
+                    message.id.name.to_string(),
+                    Message {
+                        value: message.value.as_ref().map(Uses::of),
+                        attributes,
+                    },
+                );
+            }
+        }
+        Ok(Self {
+            sources,
+            locale: cfg.default_locale.clone(),
+            messages,
+        })
+    }
+
+    // every variable a message or one of its attributes uses including through references
+    fn variables(&self, id: &str, attribute: Option<&str>) -> syn::Result<BTreeSet<String>> {
+        let mut variables = BTreeSet::new();
+        let mut seen = Vec::new();
+        let mut stack = vec![(id.to_string(), attribute.map(str::to_string))];
+        while let Some(next) = stack.pop() {
+            if seen.contains(&next) {
+                continue;
+            }
+            let (id, attribute) = &next;
+            let message = self.messages.get(id).ok_or_else(|| {
+                err!(format!(
+                    "no message `{id}` in the `{}` translations",
+                    self.locale
+                ))
+            })?;
+            let uses = match attribute {
+                Some(attr) => message.attributes.get(attr).ok_or_else(|| {
+                    err!(format!("message `{id}` has no attribute `{attr}`"))
+                })?,
+                None => message
+                    .value
+                    .as_ref()
+                    .ok_or_else(|| err!(format!("message `{id}` only has attributes")))?,
+            };
+            variables.extend(uses.variables.iter().cloned());
+            stack.extend(uses.messages.iter().cloned());
+            seen.push(next);
+        }
+        Ok(variables)
+    }
+}
+
+/// Expand the translations of a crate which every template in it uses
+///
+/// Every locale is embedded so a binary does not need the files it was built with, which
+/// also recompiles the crate when they change. They are parsed the first time a message is
+/// rendered, once for the crate, and every crate uses its own.
+pub fn expand_translations(cfg: &Config) -> syn::Result<TokenStream> {
+    let catalog = cfg.catalog()?;
+    let locales = catalog.sources.iter().map(|(locale, _)| locale);
+    let files = catalog.sources.iter().map(|(_, file)| file.as_str());
+    let default_locale = &cfg.default_locale;
+    Ok(quote! {
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        pub(crate) static __STILTS_TRANSLATIONS: ::stilts::i18n::Source = ::stilts::i18n::Source::new(
+            &[#((#locales, ::core::include_str!(#files)),)*],
+            #default_locale,
+        );
+    })
+}
+
+/// Expand a translated message into a value which can be displayed
+///
+/// The message id may name an attribute like `login.placeholder`, every argument has to
+/// be used by the message in the default locale and every variable it uses has to be given.
+pub fn expand_translate(
+    cfg: &Config,
+    id: &str,
+    args: &syn::punctuated::Punctuated<syn::FieldValue, syn::Token![,]>,
+) -> syn::Result<TokenStream> {
+    let catalog = cfg.catalog()?;
+    let (message, attribute) = match id.split_once('.') {
+        Some((message, attribute)) => (message, Some(attribute)),
+        None => (id, None),
+    };
+    let variables = catalog.variables(message, attribute)?;
+
+    let mut names = Vec::with_capacity(args.len());
+    let mut values = Vec::with_capacity(args.len());
+    for arg in args {
+        let syn::Member::Named(name) = &arg.member else {
+            return Err(err!(arg.member, "translation arguments must be named"));
+        };
+        let name = name.to_string();
+        if !variables.contains(&name) {
+            return Err(err!(
+                arg.member,
+                format!("message `{id}` does not use the argument `{name}`")
+            ));
+        }
+        names.push(name);
+        values.push(&arg.expr);
+    }
+    if let Some(missing) = variables.iter().find(|var| !names.contains(var)) {
+        return Err(err!(format!(
+            "message `{id}` requires the argument `{missing}`"
+        )));
+    }
+
+    let attribute = match attribute {
+        Some(attr) => quote! { ::core::option::Option::Some(#attr) },
+        None => quote! { ::core::option::Option::None },
+    };
+    Ok(quote! {
+        ::stilts::i18n::Message::new(
+            &crate::__STILTS_TRANSLATIONS,
+            __stilts_locale,
+            #message,
+            #attribute,
+            [#((#names, ::stilts::i18n::FluentArg::to_fluent(&(#values))),)*],
+        )
+    })
+}
@@ -0,0 +1,44 @@
+use proc_macro2::TokenStream;
+use syn::{Expr, Ident, ImplGenerics, TypeGenerics, WhereClause};
+
+pub fn expand_integration(
+    ident: &Ident,
+    impl_gen: &ImplGenerics,
+    type_gen: &TypeGenerics,
+    where_clause: &Option<&WhereClause>,
+    stream: Option<&Expr>,
+) -> TokenStream {
+    let body = match stream {
+        Some(chunk_size) => quote::quote! {
+            let mime = ::stilts::Template::mime_str(&self);
+            let mut res = ::axum::response::Response::builder()
+                .status(200);
+            if let Some(mime) = mime {
+                res = res.header(::axum::http::header::CONTENT_TYPE, ::axum::http::HeaderValue::from_static(mime));
+            }
+            res.body(::axum::body::Body::from_stream(::stilts::stream::TemplateStream::new(self, #chunk_size)))
+                .unwrap()
+        },
+        None => quote::quote! {
+            match ::stilts::Template::render(&self) {
+                Ok(content) => {
+                    let mut res = ::axum::response::Response::builder()
+                        .status(200);
+                    if let Some(mime) = ::stilts::Template::mime_str(&self) {
+                        res = res.header(::axum::http::header::CONTENT_TYPE, ::axum::http::HeaderValue::from_static(mime));
+                    }
+                    res.body(::axum::body::Body::new(content))
+                        .unwrap()
+                }
+                Err(_) => ::axum::response::IntoResponse::into_response(::axum::http::StatusCode::INTERNAL_SERVER_ERROR),
+            }
+        },
+    };
+    quote::quote! {
+        impl #impl_gen ::axum::response::IntoResponse for #ident #type_gen #where_clause {
+            fn into_response(self) -> ::axum::response::Response {
+                #body
+            }
+        }
+    }
+}
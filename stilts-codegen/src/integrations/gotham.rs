@@ -0,0 +1,51 @@
+use proc_macro2::TokenStream;
+use syn::{Expr, Ident, ImplGenerics, TypeGenerics, WhereClause};
+
+pub fn expand_integration(
+    ident: &Ident,
+    impl_gen: &ImplGenerics,
+    type_gen: &TypeGenerics,
+    where_clause: &Option<&WhereClause>,
+    stream: Option<&Expr>,
+) -> TokenStream {
+    let body = match stream {
+        Some(chunk_size) => quote::quote! {
+            let mut res = ::gotham::hyper::Response::builder()
+                .status(200);
+            if let Some(mime) = ::stilts::Template::mime_str(&self) {
+                res = res.header(::gotham::hyper::http::header::CONTENT_TYPE, ::gotham::hyper::http::HeaderValue::from_static(mime));
+            }
+            let body = ::gotham::http_body_util::BodyExt::map_err(
+                ::stilts::stream::TemplateStream::new(self, #chunk_size),
+                ::std::io::Error::other,
+            );
+            res.body(::gotham::http_body_util::BodyExt::boxed_unsync(body))
+                .unwrap()
+        },
+        None => quote::quote! {
+            match ::stilts::Template::render(&self) {
+                Ok(content) => {
+                    let mut res = ::gotham::hyper::Response::builder()
+                        .status(200);
+                    if let Some(mime) = ::stilts::Template::mime_str(&self) {
+                        res = res.header(::gotham::hyper::http::header::CONTENT_TYPE, ::gotham::hyper::http::HeaderValue::from_static(mime));
+                    }
+                    let body = ::gotham::http_body_util::BodyExt::map_err(
+                        ::gotham::http_body_util::Full::new(::gotham::bytes::Bytes::from(content)),
+                        |never: ::core::convert::Infallible| -> ::std::io::Error { match never {} },
+                    );
+                    res.body(::gotham::http_body_util::BodyExt::boxed_unsync(body))
+                        .unwrap()
+                }
+                Err(_) => ::gotham::hyper::Response::builder().status(500).body(::gotham::helpers::http::Body::default()).unwrap(),
+            }
+        },
+    };
+    quote::quote! {
+        impl #impl_gen ::gotham::handler::IntoResponse for #ident #type_gen #where_clause {
+            fn into_response(self, state: &::gotham::state::State) -> ::gotham::hyper::http::Response<::gotham::helpers::http::Body> {
+                #body
+            }
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use proc_macro2::{Ident, Span};
+use quote::quote;
+use syn::{Expr, Generics};
+
+pub fn expand_integration(
+    ident: &Ident,
+    generics: &Generics,
+    stream: Option<&Expr>,
+) -> proc_macro2::TokenStream {
+    let type_gen = generics.split_for_impl().1;
+    let mut generics = generics.clone();
+    let req_lifetime = syn::Lifetime::new("'rocket_request", Span::call_site());
+    let res_lifetime = syn::Lifetime::new("'rocket_response", Span::call_site());
+
+    generics.params.push(syn::GenericParam::Lifetime(syn::LifetimeParam::new(req_lifetime)));
+    if stream.is_none() {
+        let mut res_lifetime_param = syn::LifetimeParam::new(res_lifetime);
+
+        res_lifetime_param.colon_token = Some(syn::Token![:](Span::call_site()));
+        res_lifetime_param.bounds = syn::punctuated::Punctuated::new();
+        res_lifetime_param.bounds.push(syn::Lifetime::new("'rocket_request", Span::call_site()));
+        generics.params.push(syn::GenericParam::Lifetime(res_lifetime_param));
+    }
+
+    let (impl_gen, _, where_clause) = generics.split_for_impl();
+    match stream {
+        // streamed bodies borrow from the request so the response can not outlive it
+        Some(chunk_size) => quote! {
+            impl #impl_gen ::rocket::response::Responder<'rocket_request, 'rocket_request> for #ident #type_gen #where_clause {
+                fn respond_to(self, _request: &'rocket_request ::rocket::request::Request<'_>) -> ::rocket::response::Result<'rocket_request> {
+                    let mime = ::stilts::Template::mime_str(&self)
+                        .and_then(::rocket::http::ContentType::parse_flexible);
+                    // a template which fails to render makes reading the body fail
+                    let mut res = ::rocket::response::Response::build()
+                        .streamed_body(::stilts::stream::TemplateStream::new(self, #chunk_size))
+                        .finalize();
+                    if let Some(content_type) = mime {
+                        res.set_header(content_type);
+                    }
+                    Ok(res)
+                }
+            }
+        },
+        None => quote! {
+            impl #impl_gen ::rocket::response::Responder<'rocket_request, 'rocket_response> for #ident #type_gen #where_clause {
+                fn respond_to(self, request: &'rocket_request ::rocket::request::Request<'_>) -> ::rocket::response::Result<'rocket_response> {
+                    match ::stilts::Template::render(&self) {
+                        Ok(body) => {
+                            let mut res = ::rocket::response::Response::new();
+                            let mime = ::stilts::Template::mime_str(&self)
+                                .and_then(::rocket::http::ContentType::parse_flexible);
+                            if let Some(content_type) = mime {
+                                res.set_header(content_type);
+                            }
+                            res.set_sized_body(body.len(), ::std::io::Cursor::new(body));
+                            Ok(res)
+                        }
+                        Err(_) => {
+                            Err(::rocket::http::Status::InternalServerError)
+                        }
+                    }
+                }
+            }
+        },
+    }
+}
@@ -0,0 +1,39 @@
+use proc_macro2::TokenStream;
+use syn::{Expr, Ident, ImplGenerics, TypeGenerics, WhereClause};
+
+pub fn expand_integration(
+    ident: &Ident,
+    impl_gen: &ImplGenerics,
+    type_gen: &TypeGenerics,
+    where_clause: &Option<&WhereClause>,
+    stream: Option<&Expr>,
+) -> TokenStream {
+    // warp can not create a response body from a stream
+    if let Some(chunk_size) = stream {
+        return syn::Error::new_spanned(
+            chunk_size,
+            "`stream` is not supported by the warp integration, warp has no streamed response bodies",
+        )
+        .to_compile_error();
+    }
+    let body = quote::quote! {
+        match ::stilts::Template::render(&self) {
+            Ok(content) => {
+                if let Some(mime) = ::stilts::Template::mime_str(&self) {
+                    let reply = ::warp::reply::with_header(content, "Content-Type", mime);
+                    ::warp::reply::Reply::into_response(reply)
+                } else {
+                    ::warp::reply::Reply::into_response(content)
+                }
+            }
+            Err(_) => ::warp::reply::Reply::into_response(::warp::http::StatusCode::INTERNAL_SERVER_ERROR),
+        }
+    };
+    quote::quote! {
+        impl #impl_gen ::warp::Reply for #ident #type_gen #where_clause {
+            fn into_response(self) -> ::warp::reply::Response {
+                #body
+            }
+        }
+    }
+}
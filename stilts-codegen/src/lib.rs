@@ -0,0 +1,31 @@
+//! The code generation behind the stilts derive macro
+//!
+//! This lives outside of the proc macro crate so other tools like the stilts cli
+//! can load the config and expand templates the same way the derive macro does
+
+#![forbid(unsafe_code)]
+
+#[cfg(feature = "async")]
+mod async_io;
+pub mod config;
+pub mod context;
+#[cfg(feature = "datetime")]
+mod datetime;
+pub mod expand;
+#[cfg(feature = "i18n")]
+pub mod i18n;
+mod integrations;
+pub mod parse;
+mod pathing;
+
+pub(crate) const ATTR_NAME: &str = "stilts";
+
+macro_rules! err {
+    ($span:expr, $msg:expr) => {
+        syn::Error::new(syn::spanned::Spanned::span(&$span), $msg)
+    };
+    ($msg:expr) => {
+        syn::Error::new(proc_macro2::Span::call_site(), $msg)
+    };
+}
+pub(crate) use err;
@@ -1,7 +1,9 @@
 use proc_macro2::Span;
 use syn::{spanned::Spanned, Data, DeriveInput};
-use syn::{Attribute, Generics, Ident, LitBool, LitStr, Path};
+use syn::punctuated::Punctuated;
+use syn::{Attribute, Expr, Generics, Ident, LitBool, LitStr, Path, Token};
 
+use crate::config::Config;
 use crate::{err, ATTR_NAME};
 
 pub struct TemplateInput {
@@ -49,10 +51,18 @@ impl TemplateSource {
         }
     }
 
-    pub fn mime_type(&self) -> Option<mime_guess::mime::Mime> {
-        self.as_path()
-            .map(mime_guess::from_path)
-            .and_then(|g| g.first())
+    // the path of the variant of a file template for a locale, `terms.html` becomes `terms.de.html`
+    pub fn localized(&self, locale: &str) -> Option<String> {
+        let path = self.as_path()?;
+        let name_start = path.rfind('/').map_or(0, |i| i + 1);
+        let insert = path[name_start..]
+            .find('.')
+            .map_or(path.len(), |i| name_start + i);
+        Some(format!("{}.{locale}{}", &path[..insert], &path[insert..]))
+    }
+
+    pub fn mime_type(&self, cfg: &Config) -> Option<mime_guess::mime::Mime> {
+        self.as_path().and_then(|p| cfg.mime_type(&p))
     }
 }
 
@@ -61,6 +71,9 @@ pub struct TemplateAttrs {
     pub escape: Option<Path>,
     pub trim: Option<bool>,
     pub block: Option<String>,
+    #[allow(dead_code)] // This is used in feature gated integrations
+    pub stream: Option<Expr>,
+    pub locales: Vec<LitStr>,
 }
 
 impl TemplateAttrs {
@@ -73,6 +86,8 @@ impl TemplateAttrs {
         let mut escape = None;
         let mut trim = None;
         let mut block = None;
+        let mut stream = None;
+        let mut locales = Vec::new();
 
         for attr in attrs {
             attr.parse_nested_meta(|meta| {
@@ -101,34 +116,87 @@ impl TemplateAttrs {
                     let value: LitStr = value.parse()?;
                     block = Some(value.value());
                 }
+                if meta.path.is_ident("stream") {
+                    let chunk_size = if meta.input.peek(syn::Token![=]) {
+                        let chunk_size: Expr = meta.value()?.parse()?;
+                        if let Expr::Lit(syn::ExprLit {
+                            lit: syn::Lit::Int(size),
+                            ..
+                        }) = &chunk_size
+                        {
+                            if size.base10_parse::<usize>()? == 0 {
+                                return Err(err!(
+                                    size,
+                                    "the chunk size of a stream must not be zero"
+                                ));
+                            }
+                        }
+                        chunk_size
+                    } else {
+                        syn::parse_quote!(::stilts::stream::DEFAULT_CHUNK_SIZE)
+                    };
+                    stream = Some(chunk_size);
+                }
+                if meta.path.is_ident("locales") {
+                    let value = meta.value()?;
+                    let content;
+                    syn::bracketed!(content in value);
+                    let values = Punctuated::<LitStr, Token![,]>::parse_terminated(&content)?;
+                    locales.extend(values);
+                }
                 Ok(())
             })?;
         }
 
         let source = source.ok_or_else(|| err!(r#"templates require a `path` or `content` attribute to find the template file e.g. `#[stilts(path = "index.html")]`"#))?;
+        if let (TemplateSource::Literal(_), Some(locale)) = (&source, locales.first()) {
+            return Err(err!(locale, "locale variants require the template to have a `path`"));
+        }
         Ok(Self {
             source,
             escape,
             trim,
             block,
+            stream,
+            locales,
         })
     }
 }
 
 pub struct Field {
     pub ident: Ident,
+    pub size_hint: Option<Expr>,
+    #[allow(dead_code)] // This is used when the i18n feature is enabled
+    pub locale: bool,
 }
 
 impl Field {
     pub fn parse(field: syn::Field) -> Option<syn::Result<Self>> {
         let mut ignore = false;
-        for attr in field.attrs {
-            let _ = attr.parse_nested_meta(|meta| {
+        let mut size_hint = None;
+        let mut locale = false;
+        let attrs = field
+            .attrs
+            .into_iter()
+            .filter(|attr| attr.path().is_ident(ATTR_NAME));
+        for attr in attrs {
+            let res = attr.parse_nested_meta(|meta| {
                 if meta.path.is_ident("ignore") {
                     ignore = true;
                 }
+                if meta.path.is_ident("size_hint") {
+                    let value = meta.value()?;
+                    let value: Expr = value.parse()?;
+                    size_hint = Some(value);
+                }
+                if meta.path.is_ident("locale") {
+                    locale = true;
+                }
                 Ok(())
             });
+            if let Err(e) = res {
+                return Some(Err(e));
+            }
         }
         if ignore {
             return None;
@@ -139,6 +207,10 @@ impl Field {
                 "only structs with named fields are supported",
             )));
         };
-        Some(Ok(Self { ident }))
+        Some(Ok(Self {
+            ident,
+            size_hint,
+            locale,
+        }))
     }
 }
@@ -0,0 +1,26 @@
+use std::borrow::Cow;
+
+use cargo_metadata::camino::{Utf8Path, Utf8PathBuf};
+
+// `$CARGO_MANIFEST_DIR` is the directory of the package the config belongs to so paths
+// expand the same outside of cargo, other variables are read from the environment
+pub fn expand_path(path: impl Into<Utf8PathBuf>, manifest_dir: &Utf8Path) -> Utf8PathBuf {
+    let path = path.into();
+    let path: Utf8PathBuf = path
+        .components()
+        .map(|c| match c.as_str().strip_prefix('$') {
+            Some("CARGO_MANIFEST_DIR") => Cow::Borrowed(manifest_dir),
+            Some(var) => {
+                let s = std::env::var(var).unwrap_or_else(|_| c.as_str().to_string());
+                Cow::Owned(Utf8PathBuf::from(s))
+            }
+            None => Cow::Borrowed(Utf8Path::new(c.as_str())),
+        })
+        .collect();
+
+    if path.is_relative() {
+        manifest_dir.join(path)
+    } else {
+        path
+    }
+}
@@ -166,17 +166,19 @@ impl<'i, I: Stream> winnow::error::AddContext<I, Self> for Error<'i> {
     }
 }
 
+pub(crate) fn missing_end(open_expr: Located<'_>) -> Error<'_> {
+    Error::new("expected closing {% end %} expression")
+        .label("opening block expression")
+        .span(open_expr)
+}
+
 pub(crate) fn expect_end<'i>(
     open_expr: Located<'i>,
 ) -> impl FnOnce(ErrMode<Error<'i>>) -> ErrMode<Error<'i>> {
     move |errmode| {
         let slice_bad = matches!(&errmode, ErrMode::Backtrack(e) | ErrMode::Cut(e) if e.kind == Some(ErrorKind::Slice));
         if errmode.is_incomplete() || slice_bad {
-            ErrMode::Cut(
-                Error::new("expected closing {% end %} expression")
-                    .label("opening block expression")
-                    .span(open_expr),
-            )
+            ErrMode::Cut(missing_end(open_expr))
         } else {
             errmode
         }
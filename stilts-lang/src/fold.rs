@@ -0,0 +1,206 @@
+//! Rebuild the syntax tree of a template by value
+//!
+//! Each method of [`Fold`] takes ownership of one type of node and returns its replacement,
+//! by default it calls the function of the same name in this module which folds the children
+//! of the node and puts it back together.
+//!
+//! ```
+//! use stilts_lang::fold::{self, Fold};
+//! use stilts_lang::types::Item;
+//!
+//! // replace every block with its content
+//! struct Unblock;
+//!
+//! impl<'i> Fold<'i> for Unblock {
+//!     fn fold_item(&mut self, node: Item<'i>) -> Item<'i> {
+//!         match fold::fold_item(self, node) {
+//!             Item::Block(block) => Item::Content(block.name),
+//!             item => item,
+//!         }
+//!     }
+//! }
+//!
+//! let root = stilts_lang::parse_template("{% block main %}{% end %}", Default::default());
+//! let root = Unblock.fold_root(root.unwrap());
+//! assert!(matches!(&root.content[..], [Item::Content(name)] if name == "main"));
+//! ```
+
+use std::borrow::Cow;
+
+use crate::types::{
+    Expr, IfBranch, Item, ItemBlock, ItemFor, ItemIf, ItemMacro, ItemMatch, MatchArm, Root,
+};
+
+/// Fold the nodes of a template into new ones
+pub trait Fold<'i> {
+    fn fold_root(&mut self, node: Root<'i>) -> Root<'i> {
+        fold_root(self, node)
+    }
+
+    fn fold_item(&mut self, node: Item<'i>) -> Item<'i> {
+        fold_item(self, node)
+    }
+
+    fn fold_content(&mut self, node: Cow<'i, str>) -> Cow<'i, str> {
+        fold_content(self, node)
+    }
+
+    fn fold_item_block(&mut self, node: ItemBlock<'i>) -> ItemBlock<'i> {
+        fold_item_block(self, node)
+    }
+
+    fn fold_item_for(&mut self, node: ItemFor<'i>) -> ItemFor<'i> {
+        fold_item_for(self, node)
+    }
+
+    fn fold_item_if(&mut self, node: ItemIf<'i>) -> ItemIf<'i> {
+        fold_item_if(self, node)
+    }
+
+    fn fold_if_branch(&mut self, node: IfBranch<'i>) -> IfBranch<'i> {
+        fold_if_branch(self, node)
+    }
+
+    fn fold_item_match(&mut self, node: ItemMatch<'i>) -> ItemMatch<'i> {
+        fold_item_match(self, node)
+    }
+
+    fn fold_match_arm(&mut self, node: MatchArm<'i>) -> MatchArm<'i> {
+        fold_match_arm(self, node)
+    }
+
+    fn fold_item_macro(&mut self, node: ItemMacro<'i>) -> ItemMacro<'i> {
+        fold_item_macro(self, node)
+    }
+
+    fn fold_expr(&mut self, node: Expr<'i>) -> Expr<'i> {
+        fold_expr(self, node)
+    }
+}
+
+fn fold_items<'i, F>(f: &mut F, items: Vec<Item<'i>>) -> Vec<Item<'i>>
+where
+    F: Fold<'i> + ?Sized,
+{
+    items.into_iter().map(|item| f.fold_item(item)).collect()
+}
+
+pub fn fold_root<'i, F>(f: &mut F, node: Root<'i>) -> Root<'i>
+where
+    F: Fold<'i> + ?Sized,
+{
+    Root {
+        content: fold_items(f, node.content),
+    }
+}
+
+pub fn fold_item<'i, F>(f: &mut F, node: Item<'i>) -> Item<'i>
+where
+    F: Fold<'i> + ?Sized,
+{
+    match node {
+        Item::Content(content) => Item::Content(f.fold_content(content)),
+        Item::Block(block) => Item::Block(f.fold_item_block(block)),
+        Item::For(item) => Item::For(f.fold_item_for(item)),
+        Item::If(item) => Item::If(f.fold_item_if(item)),
+        Item::Match(item) => Item::Match(f.fold_item_match(item)),
+        Item::Macro(item) => Item::Macro(f.fold_item_macro(item)),
+        Item::Expr(expr) => Item::Expr(f.fold_expr(expr)),
+    }
+}
+
+pub fn fold_content<'i, F>(_f: &mut F, node: Cow<'i, str>) -> Cow<'i, str>
+where
+    F: Fold<'i> + ?Sized,
+{
+    node
+}
+
+pub fn fold_item_block<'i, F>(f: &mut F, node: ItemBlock<'i>) -> ItemBlock<'i>
+where
+    F: Fold<'i> + ?Sized,
+{
+    ItemBlock {
+        name: node.name,
+        content: fold_items(f, node.content),
+    }
+}
+
+pub fn fold_item_for<'i, F>(f: &mut F, node: ItemFor<'i>) -> ItemFor<'i>
+where
+    F: Fold<'i> + ?Sized,
+{
+    ItemFor {
+        content: fold_items(f, node.content),
+        ..node
+    }
+}
+
+pub fn fold_item_if<'i, F>(f: &mut F, node: ItemIf<'i>) -> ItemIf<'i>
+where
+    F: Fold<'i> + ?Sized,
+{
+    ItemIf {
+        cond: node.cond,
+        content: fold_items(f, node.content),
+        branch: f.fold_if_branch(node.branch),
+    }
+}
+
+pub fn fold_if_branch<'i, F>(f: &mut F, node: IfBranch<'i>) -> IfBranch<'i>
+where
+    F: Fold<'i> + ?Sized,
+{
+    match node {
+        IfBranch::ElseIf {
+            cond,
+            content,
+            branch,
+        } => IfBranch::ElseIf {
+            cond,
+            content: fold_items(f, content),
+            branch: Box::new(f.fold_if_branch(*branch)),
+        },
+        IfBranch::Else { content } => IfBranch::Else {
+            content: fold_items(f, content),
+        },
+        IfBranch::End => IfBranch::End,
+    }
+}
+
+pub fn fold_item_match<'i, F>(f: &mut F, node: ItemMatch<'i>) -> ItemMatch<'i>
+where
+    F: Fold<'i> + ?Sized,
+{
+    ItemMatch {
+        expr: node.expr,
+        arms: node.arms.into_iter().map(|arm| f.fold_match_arm(arm)).collect(),
+    }
+}
+
+pub fn fold_match_arm<'i, F>(f: &mut F, node: MatchArm<'i>) -> MatchArm<'i>
+where
+    F: Fold<'i> + ?Sized,
+{
+    MatchArm {
+        content: fold_items(f, node.content),
+        ..node
+    }
+}
+
+pub fn fold_item_macro<'i, F>(f: &mut F, node: ItemMacro<'i>) -> ItemMacro<'i>
+where
+    F: Fold<'i> + ?Sized,
+{
+    ItemMacro {
+        content: fold_items(f, node.content),
+        ..node
+    }
+}
+
+pub fn fold_expr<'i, F>(_f: &mut F, node: Expr<'i>) -> Expr<'i>
+where
+    F: Fold<'i> + ?Sized,
+{
+    node
+}
@@ -0,0 +1,306 @@
+//! Split a template into classified pieces for highlighting
+//!
+//! [`lex`] follows the same rules as the parser to find tags and the keywords in them, but
+//! never fails, a tag which does not parse is still split up as far as it can be. Whitespace
+//! inside of tags is not part of any token.
+//!
+//! ```
+//! use stilts_lang::lex::{lex, TokenKind};
+//!
+//! let tokens = lex(r#"<p>{% include "a.html" %}</p>"#, &Default::default());
+//! let kinds = tokens.iter().map(|t| t.kind).collect::<Vec<_>>();
+//! assert_eq!(kinds, [
+//!     TokenKind::Content,
+//!     TokenKind::Open,
+//!     TokenKind::Keyword,
+//!     TokenKind::Path,
+//!     TokenKind::Close,
+//!     TokenKind::Content,
+//! ]);
+//! assert_eq!(tokens[3].text, r#""a.html""#);
+//! ```
+
+use std::ops::Range;
+
+use crate::Delims;
+
+/// What a piece of a template is
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    /// Template content outside of tags
+    Content,
+    /// The delimiter opening a tag
+    Open,
+    /// The delimiter closing a tag
+    Close,
+    /// A keyword of the template language like `block`, `for`, `in`, `when` or `end`
+    Keyword,
+    /// A name which is not rust code, like the name of a block or macro, or a message id
+    Name,
+    /// The string literal path of an `extends` or `include`, including the quotes
+    Path,
+    /// Rust code
+    Rust,
+}
+
+/// A classified piece of a template
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Token<'i> {
+    pub kind: TokenKind,
+    /// Where the token is in the template
+    pub span: Range<usize>,
+    pub text: &'i str,
+}
+
+/// Split a template into tokens which cover all of it apart from the whitespace in tags
+pub fn lex<'i>(input: &'i str, delims: &Delims) -> Vec<Token<'i>> {
+    let mut lexer = Lexer {
+        input,
+        tokens: Vec::new(),
+    };
+    let (open, close) = (delims.open(), delims.close());
+    let mut pos = 0;
+    while pos < input.len() {
+        let Some(start) = input[pos..].find(open).map(|i| i + pos) else {
+            break;
+        };
+        let inner = start + open.len();
+        let Some(end) = find_close(&input.as_bytes()[inner..], close.as_bytes()) else {
+            // a tag which is never closed is content
+            break;
+        };
+        let end = end + inner;
+        lexer.push(TokenKind::Content, pos..start);
+        lexer.push(TokenKind::Open, start..inner);
+        lexer.tag(inner, end);
+        lexer.push(TokenKind::Close, end..end + close.len());
+        pos = end + close.len();
+    }
+    lexer.push(TokenKind::Content, pos..input.len());
+    lexer.tokens
+}
+
+struct Lexer<'i> {
+    input: &'i str,
+    tokens: Vec<Token<'i>>,
+}
+
+impl<'i> Lexer<'i> {
+    fn push(&mut self, kind: TokenKind, span: Range<usize>) {
+        if !span.is_empty() {
+            let text = &self.input[span.clone()];
+            self.tokens.push(Token { kind, span, text });
+        }
+    }
+
+    // the offset of the first character after any whitespace
+    fn skip_space(&self, pos: usize, end: usize) -> usize {
+        let text = &self.input[pos..end];
+        pos + text.len() - text.trim_start().len()
+    }
+
+    // push the rest of a tag as rust code without the whitespace around it
+    fn rust(&mut self, pos: usize, end: usize) {
+        let pos = self.skip_space(pos, end);
+        let end = pos + self.input[pos..end].trim_end().len();
+        self.push(TokenKind::Rust, pos..end);
+    }
+
+    // the identifier at `pos`, pushed as a token of `kind`
+    fn ident(&mut self, kind: TokenKind, pos: usize, end: usize) -> usize {
+        let len = ident_len(&self.input[pos..end]);
+        self.push(kind, pos..pos + len);
+        pos + len
+    }
+
+    // the keyword at `pos` if it is followed by whitespace like the parser requires
+    fn keyword(&self, pos: usize, end: usize, keyword: &str) -> bool {
+        let text = &self.input[pos..end];
+        ident_len(text) == keyword.len()
+            && text.starts_with(keyword)
+            && text[keyword.len()..].starts_with(char::is_whitespace)
+    }
+
+    // split the inside of a tag up the same way the parser reads it
+    fn tag(&mut self, pos: usize, end: usize) {
+        let pos = self.skip_space(pos, end);
+        let text = &self.input[pos..end];
+        let word = &text[..ident_len(text)];
+        let after = pos + word.len();
+        let keyword = |lexer: &mut Self| lexer.push(TokenKind::Keyword, pos..after);
+
+        match word {
+            "end" | "else" if text[word.len()..].trim().is_empty() => keyword(self),
+            "else" if self.keyword(self.skip_space(after, end), end, "if") => {
+                keyword(self);
+                let pos = self.skip_space(after, end);
+                self.push(TokenKind::Keyword, pos..pos + 2);
+                self.rust(pos + 2, end);
+            }
+            "super" if text[word.len()..].trim() == "()" => {
+                let end = pos + text.trim_end().len();
+                self.push(TokenKind::Keyword, pos..end);
+            }
+            "block" if self.keyword(pos, end, word) => {
+                keyword(self);
+                let pos = self.skip_space(after, end);
+                let pos = self.ident(TokenKind::Name, pos, end);
+                self.rust(pos, end);
+            }
+            "macro" | "call" if self.keyword(pos, end, word) => {
+                keyword(self);
+                let pos = self.skip_space(after, end);
+                let pos = self.ident(TokenKind::Name, pos, end);
+                self.rust(pos, end);
+            }
+            "extends" | "include" | "t" if self.keyword(pos, end, word) => {
+                let at = self.skip_space(after, end);
+                let Some(len) = literal_len(&self.input.as_bytes()[at..end], None) else {
+                    // `t` is only a keyword when a message id follows it
+                    return self.rust(pos, end);
+                };
+                keyword(self);
+                let kind = match word {
+                    "t" => TokenKind::Name,
+                    _ => TokenKind::Path,
+                };
+                self.push(kind, at..at + len);
+                self.rust(at + len, end);
+            }
+            "for" if self.keyword(pos, end, word) => {
+                keyword(self);
+                self.split(after, end, "in");
+            }
+            "when" if self.keyword(pos, end, word) => {
+                keyword(self);
+                self.split(after, end, "if");
+            }
+            "if" | "match" if self.keyword(pos, end, word) => {
+                keyword(self);
+                self.rust(after, end);
+            }
+            _ => self.rust(pos, end),
+        }
+    }
+
+    // rust code with a keyword in the middle like the `in` of a for loop
+    fn split(&mut self, pos: usize, end: usize, keyword: &str) {
+        match find_word(&self.input[pos..end], keyword) {
+            Some(at) => {
+                let at = pos + at;
+                self.rust(pos, at);
+                self.push(TokenKind::Keyword, at..at + keyword.len());
+                self.rust(at + keyword.len(), end);
+            }
+            None => self.rust(pos, end),
+        }
+    }
+}
+
+fn ident_len(text: &str) -> usize {
+    text.find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(text.len())
+}
+
+fn is_ident(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_' || byte >= 0x80
+}
+
+// the length of the string or char literal at the start of `s`, `prev` is the byte before it
+// so the `r` at the end of an identifier is not taken as a raw string
+pub(crate) fn literal_len(s: &[u8], prev: Option<u8>) -> Option<usize> {
+    if prev.is_some_and(is_ident) {
+        return None;
+    }
+    let prefix = match s {
+        [b'b' | b'c', b'r', ..] => 2,
+        [b'b' | b'c' | b'r', ..] => 1,
+        _ => 0,
+    };
+    let raw = s[..prefix].contains(&b'r');
+    let hashes = match raw {
+        true => s[prefix..].iter().take_while(|b| **b == b'#').count(),
+        false => 0,
+    };
+    let start = prefix + hashes;
+    match s.get(start)? {
+        b'"' if raw => {
+            let close = s[start + 1..]
+                .windows(hashes + 1)
+                .position(|w| w[0] == b'"' && w[1..].iter().all(|b| *b == b'#'))?;
+            Some(start + 1 + close + hashes + 1)
+        }
+        b'"' => {
+            let mut i = start + 1;
+            while i < s.len() {
+                match s[i] {
+                    b'\\' => i += 2,
+                    b'"' => return Some(i + 1),
+                    _ => i += 1,
+                }
+            }
+            None
+        }
+        b'\'' if !raw && prefix != 2 => {
+            // a char literal, anything else is a lifetime or label
+            let len = match s.get(start + 1)? {
+                // an escape like `\n`, `\x7f` or `\u{1f600}`
+                b'\\' => match s.get(start + 2)? {
+                    b'x' => 4,
+                    b'u' => s.get(start + 3..)?.iter().take(9).position(|b| *b == b'}')? + 3,
+                    _ => 2,
+                },
+                b if *b < 0x80 => 1,
+                b if *b < 0xe0 => 2,
+                b if *b < 0xf0 => 3,
+                _ => 4,
+            };
+            let close = start + 1 + len;
+            (s.get(close) == Some(&b'\'')).then_some(close + 1)
+        }
+        _ => None,
+    }
+}
+
+// the offset of the closing delimiter of a tag, delimiters inside of rust strings are skipped
+pub(crate) fn find_close(s: &[u8], close: &[u8]) -> Option<usize> {
+    let mut i = 0;
+    while i < s.len() {
+        if s[i..].starts_with(close) {
+            return Some(i);
+        }
+        match literal_len(&s[i..], i.checked_sub(1).map(|p| s[p])) {
+            Some(len) => i += len,
+            None => i += 1,
+        }
+    }
+    None
+}
+
+// the offset of a keyword in rust code which is not in a string or inside of brackets
+fn find_word(text: &str, word: &str) -> Option<usize> {
+    let s = text.as_bytes();
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < s.len() {
+        let prev = i.checked_sub(1).map(|p| s[p]);
+        if let Some(len) = literal_len(&s[i..], prev) {
+            i += len;
+            continue;
+        }
+        match s[i] {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => depth = depth.saturating_sub(1),
+            _ if depth == 0
+                && !prev.is_some_and(is_ident)
+                && s[i..].starts_with(word.as_bytes())
+                && !s.get(i + word.len()).copied().is_some_and(is_ident) =>
+            {
+                return Some(i);
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
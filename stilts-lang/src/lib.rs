@@ -1,20 +1,31 @@
 //! Stilts Lang is the parser for the stilts language
 //! Parse a full template with [`parse_template`] the
 //! output is is an ast whose definition is in the [`types`] module
+//! and which can be walked with the [`visit`], [`visit_mut`] and [`fold`] modules.
+//! Every node can be printed back into template source with `to_source` or [`Display`](std::fmt::Display),
+//! the [`print`] module has hooks for printing the rust code in tags differently,
+//! and the [`lex`] module splits source into classified tokens for highlighting.
 
 pub use error::Error;
 pub use located::Located;
 pub use state::Delims;
-use state::State;
+use state::Context;
 use types::Root;
 
-pub(crate) type Input<'i> = winnow::Stateful<Located<'i>, State>;
+pub(crate) type Input<'i> = winnow::Stateful<Located<'i>, Context<'i>>;
 
 mod error;
+pub mod fold;
+pub mod lex;
 mod located;
 mod parse;
+pub mod print;
+#[cfg(feature = "serde")]
+mod serde_syn;
 mod state;
 pub mod types;
+pub mod visit;
+pub mod visit_mut;
 
 /// Parse a template
 ///
@@ -25,16 +36,41 @@ pub fn parse_template(input: &str, delims: Delims) -> Result<Root<'_>, Error<'_>
     use winnow::Parser;
     let input = winnow::Stateful {
         input: Located::new(input),
-        state: State::default(),
+        state: Context::default(),
     };
     parse::root(&delims)
         .parse(input)
         .map_err(|err| err.into_inner())
 }
 
+/// Parse a template and keep going after errors
+///
+/// Broken tags are skipped and expressions missing their `{% end %}` are closed at the end
+/// of the template, so the rest of the template is still parsed. This returns the tree of
+/// everything that could be parsed along with every error found on the way, which is what
+/// editors and other tools showing all of the problems in a template need.
+pub fn parse_template_recovering(input: &str, delims: Delims) -> (Root<'_>, Vec<Error<'_>>) {
+    use winnow::Parser;
+    let mut input = winnow::Stateful {
+        input: Located::new(input),
+        state: Context {
+            errors: Some(Vec::new()),
+            ..Context::default()
+        },
+    };
+    let root = parse::root(&delims).parse_next(&mut input);
+    let mut errors = input.state.errors.take().unwrap_or_default();
+    let root = root.unwrap_or_else(|err| {
+        errors.extend(err.into_inner());
+        Root { content: Vec::new() }
+    });
+    (root, errors)
+}
+
 #[cfg(test)]
 mod test {
-    use crate::{parse_template, types::{Expr, IfBranch, Item, ItemBlock, ItemFor, ItemIf, ItemMacro, ItemMatch, MatchArm, Root}, Delims};
+    use crate::lex::{lex, TokenKind};
+    use crate::{parse_template, parse_template_recovering, types::{Expr, IfBranch, Item, ItemBlock, ItemFor, ItemIf, ItemMacro, ItemMatch, MatchArm, Root}, Delims};
     use pretty_assertions::assert_eq;
     use syn::{parse::Parser as _, punctuated::Punctuated};
 
@@ -211,4 +247,153 @@ mod test {
         };
         assert_eq!(res, expects);
     }
+
+    #[test]
+    pub fn print_round_trip() {
+        let root = parse_template(TEMPLATE, Delims::default()).unwrap();
+        let printed = root.to_string();
+        assert_eq!(parse_template(&printed, Delims::default()).unwrap(), root);
+
+        let template = r#"{% for (i, x) in xs.iter().enumerate() %}{% include "a \"b\".html" { x, y: 1 } %}{% end %}"#;
+        let root = parse_template(template, Delims::default()).unwrap();
+        let delims = Delims::new("<%", "%>");
+        let printed = root.to_source(&delims);
+        assert!(printed.starts_with("<% for (i , x) in"), "{printed}");
+        assert_eq!(parse_template(&printed, delims).unwrap(), root);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    pub fn serde_round_trip() {
+        let root = parse_template(TEMPLATE, Delims::default()).unwrap();
+        let json = serde_json::to_value(&root).unwrap();
+        assert_eq!(json["content"][0], serde_json::json!({
+            "type": "expr",
+            "value": { "type": "extends", "value": "base.html" },
+        }));
+        assert_eq!(json["content"][4]["value"]["args"], serde_json::json!(["time : std :: time :: Duration"]));
+        assert_eq!(serde_json::from_value::<Root>(json).unwrap(), root);
+    }
+
+    #[test]
+    pub fn recover_broken_tags() {
+        let template = r#"{% block main %}{% for x in %}a{% end %}{% x + %}{% include "a" {,} %}b{% end %}"#;
+        let (root, errors) = parse_template_recovering(template, Delims::default());
+        assert_eq!(errors.len(), 3, "{errors:?}");
+        assert!(parse_template(template, Delims::default()).is_err());
+        assert_eq!(root, Root {
+            content: vec![Item::Block(ItemBlock {
+                name: "main".into(),
+                content: vec![Item::Content("b".into())],
+            })],
+        });
+    }
+
+    #[test]
+    pub fn recover_missing_end() {
+        let template = "{% if a %}1{% else %}{% block b %}2";
+        let (root, errors) = parse_template_recovering(template, Delims::default());
+        let messages = errors.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(messages, ["expected closing {% end %} expression"; 2]);
+        assert_eq!(root, Root {
+            content: vec![Item::If(ItemIf {
+                cond: syn::parse_str("a").unwrap(),
+                content: vec![Item::Content("1".into())],
+                branch: IfBranch::Else {
+                    content: vec![Item::Block(ItemBlock {
+                        name: "b".into(),
+                        content: vec![Item::Content("2".into())],
+                    })],
+                },
+            })],
+        });
+    }
+
+    #[test]
+    pub fn recover_nothing_from_valid_template() {
+        let (root, errors) = parse_template_recovering(TEMPLATE, Delims::default());
+        assert!(errors.is_empty());
+        assert_eq!(root, parse_template(TEMPLATE, Delims::default()).unwrap());
+    }
+
+    #[test]
+    pub fn close_delimiter_in_string() {
+        let root = parse_template(r#"{% "a %} b" %}{% '%' %}{% '\u{7d}' %}"#, Delims::default()).unwrap();
+        assert_eq!(root, Root {
+            content: vec![
+                Item::Expr(Expr::Expr(syn::parse_str(r#""a %} b""#).unwrap())),
+                Item::Expr(Expr::Expr(syn::parse_str("'%'").unwrap())),
+                Item::Expr(Expr::Expr(syn::parse_str("'\\u{7d}'").unwrap())),
+            ],
+        });
+        // an unfinished char escape is not a literal
+        let root = parse_template("{% '\\", Delims::default()).unwrap();
+        assert_eq!(root, Root { content: vec![Item::Content("{% '\\".into())] });
+    }
+
+    #[test]
+    pub fn close_delimiter_in_raw_string() {
+        let root = parse_template(r##"{% r"%}" %}{% r#"a "%} b"# %}{% br#"%}"# %}"##, Delims::default()).unwrap();
+        assert_eq!(root, Root {
+            content: vec![
+                Item::Expr(Expr::Expr(syn::parse_str(r#"r"%}""#).unwrap())),
+                Item::Expr(Expr::Expr(syn::parse_str(r##"r#"a "%} b"#"##).unwrap())),
+                Item::Expr(Expr::Expr(syn::parse_str(r##"br#"%}"#"##).unwrap())),
+            ],
+        });
+    }
+
+    #[test]
+    pub fn lifetime_before_close_delimiter() {
+        let root = parse_template("{% continue 'rows%}{% f::<'a>('b') %}", Delims::default()).unwrap();
+        assert_eq!(root, Root {
+            content: vec![
+                Item::Expr(Expr::Expr(syn::parse_str("continue 'rows").unwrap())),
+                Item::Expr(Expr::Expr(syn::parse_str("f::<'a>('b')").unwrap())),
+            ],
+        });
+    }
+
+    #[test]
+    pub fn unterminated_literal() {
+        // the tag ends at the first close delimiter when a literal never ends, which is
+        // not valid rust so the tag is kept as content
+        for template in [r#"{% "a %} b"#, r##"{% r#"a "%} b %}"##, "{% '%} x"] {
+            let root = parse_template(template, Delims::default()).unwrap();
+            assert_eq!(root, Root { content: vec![Item::Content(template.into())] });
+        }
+    }
+
+    #[test]
+    pub fn lex_tags() {
+        use TokenKind::*;
+        let template = r#"<p>{% for (a, b) in x.iter() %}{% block main %}{% "%}" %}{% end %}{% match y %}{% when Some(z) if z > 1 %}{% t "hi" { n: 1 } %}{% super() %}"#;
+        let tokens = lex(template, &Delims::default())
+            .into_iter()
+            .map(|t| (t.kind, t.text))
+            .collect::<Vec<_>>();
+        assert_eq!(tokens, [
+            (Content, "<p>"),
+            (Open, "{%"), (Keyword, "for"), (Rust, "(a, b)"), (Keyword, "in"), (Rust, "x.iter()"), (Close, "%}"),
+            (Open, "{%"), (Keyword, "block"), (Name, "main"), (Close, "%}"),
+            (Open, "{%"), (Rust, r#""%}""#), (Close, "%}"),
+            (Open, "{%"), (Keyword, "end"), (Close, "%}"),
+            (Open, "{%"), (Keyword, "match"), (Rust, "y"), (Close, "%}"),
+            (Open, "{%"), (Keyword, "when"), (Rust, "Some(z)"), (Keyword, "if"), (Rust, "z > 1"), (Close, "%}"),
+            (Open, "{%"), (Keyword, "t"), (Name, r#""hi""#), (Rust, "{ n: 1 }"), (Close, "%}"),
+            (Open, "{%"), (Keyword, "super()"), (Close, "%}"),
+        ]);
+
+        let template = "{% t.x %}{% r#\"%}\"# %}<% extends \"a\" %>";
+        let tokens = lex(template, &Delims::default());
+        let kinds = tokens.iter().map(|t| t.kind).collect::<Vec<_>>();
+        assert_eq!(kinds, [Open, Rust, Close, Open, Rust, Close, Content]);
+        assert_eq!(&template[tokens[1].span.clone()], "t.x");
+
+        let tokens = lex("{% t '\\%}{% '\\", &Delims::default())
+            .into_iter()
+            .map(|t| (t.kind, t.text))
+            .collect::<Vec<_>>();
+        assert_eq!(tokens, [(Open, "{%"), (Rust, "t '\\"), (Close, "%}"), (Content, "{% '\\")]);
+    }
 }
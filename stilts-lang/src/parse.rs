@@ -3,14 +3,15 @@ use std::sync::LazyLock;
 
 use aho_corasick::AhoCorasick;
 use winnow::ascii::{multispace0, multispace1, take_escaped};
-use winnow::combinator::{alt, cut_err, eof, opt, peek, preceded, repeat, repeat_till, terminated, trace};
-use winnow::error::ParserError;
-use winnow::stream::{AsChar, Compare, Stream, StreamIsPartial};
-use winnow::token::{any, none_of, one_of, take, take_until, take_while};
+use winnow::combinator::{alt, cut_err, eof, fail, opt, peek, preceded, repeat, repeat_till, terminated, trace};
+use winnow::error::{ErrMode, ParserError};
+use winnow::stream::{AsBytes, AsChar, Compare, Stream, StreamIsPartial};
+use winnow::token::{any, none_of, one_of, rest, take, take_until, take_while};
 use winnow::Parser;
 
-use crate::error::{expect_end, At, Msg};
-use crate::state::State;
+use crate::lex::find_close;
+use crate::error::{expect_end, missing_end, At, Msg};
+use crate::state::{Context, State};
 use crate::types::{
     Expr, ForExpr, IfBranch, IncludesArgs, Item, ItemBlock, ItemFor, ItemIf, ItemMacro, ItemMatch, MacroCallExpr, MacroExpr, MatchArm, MatchArmExpr, Root
 };
@@ -22,12 +23,14 @@ type PResult<'i, T> = winnow::PResult<T, Error<'i>>;
 pub fn root<'i>(delims: &Delims) -> impl FnMut(&mut Input<'i>) -> PResult<'i, Root<'i>> {
     let delims = delims.clone();
     move |input| {
-        let mut first = opt(preceded(multispace0, item(&delims))).parse_next(input)?;
-        input.state &= !State::ALLOW_EXTEND; // Do not allow extend after first item!
+        let mut first = opt(preceded(multispace0, recovering_item(&delims)))
+            .parse_next(input)?
+            .flatten();
+        input.state.flags &= !State::ALLOW_EXTEND; // Do not allow extend after first item!
         
-        let mut content = repeat(0.., item(&delims))
+        let mut content = repeat(0.., recovering_item(&delims))
             .fold(move || first.take().map(|v| vec![v]).unwrap_or_default(), |mut acc, item| {
-                acc.push(item);
+                acc.extend(item);
                 acc
             })
             .parse_next(input)?;
@@ -79,15 +82,15 @@ pub fn item_block<'i>(delims: &Delims) -> impl Parser<Input<'i>, ItemBlock<'i>,
         .context(At(input.here()))
         .parse_next(input)?;
 
-        let saved = input.state;
-        input.state |= State::ALLOW_SUPERCALL;
-        //input.state &= !State::ALLOW_BLOCK;
+        let saved = input.state.flags;
+        input.state.flags |= State::ALLOW_SUPERCALL;
+        //input.state.flags &= !State::ALLOW_BLOCK;
 
-        let content = cut_err(items_till(&delims, end(&delims)))
+        let content = cut_err(items_till(&delims, closing(span, end(&delims), || ())))
             .map(|v| v.0)
             .parse_next(input)
             .map_err(expect_end(span))?;
-        input.state = saved;
+        input.state.flags = saved;
         Ok(ItemBlock {
             name: Cow::Borrowed(name.content()),
             content,
@@ -105,7 +108,7 @@ pub fn item_for<'i>(delims: &Delims) -> impl Parser<Input<'i>, ItemFor<'i>, Erro
         .with_taken()
         .parse_next(input)?;
 
-        let content = cut_err(items_till(&delims, end(&delims)))
+        let content = cut_err(items_till(&delims, closing(span, end(&delims), || ())))
             .map(|v| v.0)
             .parse_next(input)
             .map_err(expect_end(span))?;
@@ -127,7 +130,7 @@ pub fn item_if<'i>(delims: &Delims) -> impl Parser<Input<'i>, ItemIf<'i>, Error<
         )
         .with_taken()
         .parse_next(input)?;
-        let (content, branch) = items_till(&delims, if_branch(&delims))
+        let (content, branch) = items_till(&delims, closing(span, if_branch(&delims), || IfBranch::End))
             .parse_next(input)
             .map_err(expect_end(span))?;
         Ok(ItemIf {
@@ -151,7 +154,8 @@ fn if_branch<'i>(delims: &Delims) -> impl Parser<Input<'i>, IfBranch<'i>, Error<
                 )
                 .with_taken()
                 .parse_next(input)?;
-                let (content, branch) = items_till(&delims1, if_branch(&delims1))
+                let (content, branch) =
+                    items_till(&delims1, closing(span, if_branch(&delims1), || IfBranch::End))
                     .parse_next(input)
                     .map_err(expect_end(span))?;
                 Ok(IfBranch::ElseIf {
@@ -164,7 +168,7 @@ fn if_branch<'i>(delims: &Delims) -> impl Parser<Input<'i>, IfBranch<'i>, Error<
                 let span = delimited(&delims2, ("else", multispace0))
                     .take()
                     .parse_next(input)?;
-                let content = items_till(&delims2, end(&delims2))
+                let content = items_till(&delims2, closing(span, end(&delims2), || ()))
                     .map(|v| v.0)
                     .parse_next(input)
                     .map_err(expect_end(span))?;
@@ -197,7 +201,7 @@ pub fn item_match<'i>(delims: &Delims) -> impl Parser<Input<'i>, ItemMatch<'i>,
 
                 let content = items_till(
                     &delims2,
-                    peek(alt((match_arm_test(&delims2), end(&delims2)))),
+                    peek(alt((match_arm_test(&delims2), end(&delims2), recovered_eof))),
                 )
                 .map(|v| v.0)
                 .parse_next(input)
@@ -208,7 +212,7 @@ pub fn item_match<'i>(delims: &Delims) -> impl Parser<Input<'i>, ItemMatch<'i>,
                     content,
                 })
             }),
-            end(&delims),
+            closing(span, end(&delims), || ()),
         )
         .map(|(arms, _)| arms)
         .parse_next(input)
@@ -230,7 +234,7 @@ pub fn item_macro<'i>(delims: &Delims) -> impl Parser<Input<'i>, ItemMacro<'i>,
                 &delims,
                 preceded(("macro", multispace1), cut_err(parse_syn::<MacroExpr>)),
             ).with_taken().parse_next(input)?;
-            let content = items_till(&delims, end(&delims)).map(|v| v.0).parse_next(input)
+            let content = items_till(&delims, closing(span, end(&delims), || ())).map(|v| v.0).parse_next(input)
                 .map_err(expect_end(span))?;
             Ok(ItemMacro {
                 name: mcr.name,
@@ -261,6 +265,21 @@ pub fn item_expr<'i>(delims: &Delims) -> impl Parser<Input<'i>, Expr<'i>, Error<
         Ok(Expr::Include { reference, args })
     }
 
+    #[allow(clippy::result_large_err)]
+    fn expr_translate<'i>(input: &mut Located<'i>) -> PResult<'i, Expr<'i>> {
+        // only commit once the message id starts so `t` can still be used as a variable
+        let id = preceded(("t", multispace1, peek('"')), cut_err(string_contents))
+            .context(Msg("unable to parse translate expression"))
+            .context(At(input.here()))
+            .parse_next(input)?;
+        let args = if preceded(multispace1::<_, Error<'i>>, peek('{')).parse_next(input).is_ok() {
+            cut_err(parse_syn).parse_next(input)?
+        } else {
+            IncludesArgs { args: syn::punctuated::Punctuated::new() }
+        }.args;
+        Ok(Expr::Translate { id, args })
+    }
+
     fn expr_super_call<'i>(input: &mut Located<'i>) -> PResult<'i, ()> {
         ("super", "(", multispace0, ")", multispace0).void()
             .parse_next(input)
@@ -276,6 +295,7 @@ pub fn item_expr<'i>(delims: &Delims) -> impl Parser<Input<'i>, Expr<'i>, Error<
             expr_extends.map(Expr::Extends),
             expr_include,
             expr_super_call.map(|_| Expr::SuperCall),
+            expr_translate,
             expr_macro_call.map(|call| Expr::MacroCall {
                 name: call.name,
                 args: call.args,
@@ -301,7 +321,101 @@ where
     P: Parser<Input<'i>, O, Error<'i>>,
 {
     let delims = delims.clone();
-    move |input| repeat_till(0.., item(&delims), terminate.by_ref()).parse_next(input)
+    move |input| {
+        repeat_till(0.., recovering_item(&delims), terminate.by_ref())
+            .map(|(items, o): (Vec<_>, _)| (items.into_iter().flatten().collect(), o))
+            .parse_next(input)
+    }
+}
+
+/// An item, when recovering a broken tag is skipped instead and nothing is returned
+// the recovering parsers share the error type of the rest of the parser
+#[allow(clippy::result_large_err)]
+fn recovering_item<'i>(
+    delims: &Delims,
+) -> impl FnMut(&mut Input<'i>) -> PResult<'i, Option<Item<'i>>> {
+    let delims = delims.clone();
+    let mut item = item(&delims);
+    move |input| {
+        let start = input.checkpoint();
+        let err = match item.parse_next(input) {
+            Ok(item) => return Ok(Some(item)),
+            Err(err) if !input.state.recovering() => return Err(err),
+            Err(err) => err,
+        };
+        input.reset(&start);
+
+        // content can not fail to parse, so this is a tag unless the input ended
+        if input.eof_offset() == 0 {
+            return Err(err);
+        }
+        let Ok(tag) = delimited(&delims, rest::<_, Error<'i>>).take().parse_next(input) else {
+            // a tag which is never closed is content, like at the end of a template
+            input.reset(&start);
+            let content = rest.parse_next(input)?;
+            return Ok(Some(Item::Content(Cow::Borrowed(content.content()))));
+        };
+        let err = match err {
+            ErrMode::Cut(err) => err,
+            // every alternative backtracked, the error of parsing it as rust is the most useful
+            _ => {
+                let mut tag_input = winnow::Stateful { input: tag, state: Context::default() };
+                match item_expr(&delims).parse_next(&mut tag_input) {
+                    Err(ErrMode::Backtrack(err) | ErrMode::Cut(err)) => err,
+                    _ => Error::new("unable to parse expression").span(tag),
+                }
+            }
+        };
+        input.state.recover(err);
+
+        // skip past the end of a broken tag which has content, along with its branches
+        let inner = tag.content()[delims.open().len()..].trim_start();
+        let keyword = inner.split(|c: char| c.is_whitespace() || c == '(').next().unwrap_or_default();
+        if matches!(keyword, "block" | "for" | "if" | "match" | "macro") || keyword.starts_with('\'') {
+            let branch = delimited(&delims, alt(("else", "when")).void());
+            let mut terminate = closing(tag, alt((end(&delims).value(true), branch.value(false))), || true);
+            while !items_till(&delims, terminate.by_ref()).parse_next(input)?.1 {}
+        }
+        Ok(None)
+    }
+}
+
+/// The tag closing an expression opened by `open_expr`
+///
+/// When recovering the end of the input closes the expression as well, giving `missing`.
+#[allow(clippy::result_large_err)]
+fn closing<'i, P, O>(
+    open_expr: Located<'i>,
+    mut terminate: P,
+    missing: impl Fn() -> O,
+) -> impl Parser<Input<'i>, O, Error<'i>>
+where
+    P: Parser<Input<'i>, O, Error<'i>>,
+{
+    move |input: &mut Input<'i>| {
+        let start = input.checkpoint();
+        match terminate.parse_next(input) {
+            Err(ErrMode::Backtrack(err)) => {
+                input.reset(&start);
+                if input.eof_offset() == 0 && input.state.recover(missing_end(open_expr)) {
+                    Ok(missing())
+                } else {
+                    Err(ErrMode::Backtrack(err))
+                }
+            }
+            res => res,
+        }
+    }
+}
+
+/// The end of the input, only when recovering
+#[allow(clippy::result_large_err)]
+fn recovered_eof<'i>(input: &mut Input<'i>) -> PResult<'i, ()> {
+    if input.state.recovering() {
+        eof.void().parse_next(input)
+    } else {
+        fail.parse_next(input)
+    }
 }
 
 fn end<'i>(delims: &Delims) -> impl Parser<Input<'i>, (), Error<'i>> {
@@ -351,7 +465,8 @@ where
         + StreamIsPartial
         + Compare<char>
         + for<'a> Compare<&'a str>
-        + for<'a> winnow::stream::FindSlice<&'a str>,
+        + for<'a> winnow::stream::FindSlice<&'a str>
+        + AsBytes,
     I::Token: AsChar + Clone,
     E: ParserError<I>,
     P: Parser<<I as Stream>::Slice, O, E>,
@@ -359,7 +474,11 @@ where
     let delims = delims.clone();
     move |input| {
         (delims.open(), multispace0).parse_next(input)?;
-        let mut content = take_until(1.., delims.close()).parse_next(input)?;
+        // the closing delimiter can be inside of a rust string in the tag
+        let mut content = match find_close(input.as_bytes(), delims.close().as_bytes()) {
+            Some(offset) if offset > 0 => input.next_slice(offset),
+            _ => take_until(1.., delims.close()).parse_next(input)?,
+        };
         delims.close().parse_next(input)?;
         parser.parse_next(&mut content)
     }
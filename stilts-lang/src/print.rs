@@ -0,0 +1,275 @@
+//! Turning the syntax tree back into template source
+//!
+//! By default rust code is printed from its tokens, so the spacing inside of tags is not
+//! kept, but parsing the printed source gives back the same tree. Implement [`PrintRust`]
+//! and print with `to_source_with` to write the rust code some other way, like a formatter.
+
+use std::fmt::{self, Write};
+
+use quote::ToTokens;
+use syn::punctuated::Punctuated;
+use syn::Token;
+
+use crate::types::{
+    Expr, IfBranch, Item, ItemBlock, ItemFor, ItemIf, ItemMacro, ItemMatch, MatchArm, Root,
+};
+use crate::Delims;
+
+fn tokens(node: &impl ToTokens) -> String {
+    node.to_token_stream().to_string()
+}
+
+/// How the rust code inside of tags is printed, by default from its tokens
+pub trait PrintRust {
+    fn expr(&self, expr: &syn::Expr) -> String {
+        tokens(expr)
+    }
+
+    fn stmt(&self, stmt: &syn::Stmt) -> String {
+        tokens(stmt)
+    }
+
+    fn pat(&self, pat: &syn::Pat) -> String {
+        tokens(pat)
+    }
+
+    /// The arguments of a macro without the parentheses around them
+    fn fn_args(&self, args: &Punctuated<syn::FnArg, Token![,]>) -> String {
+        tokens(args)
+    }
+
+    /// The arguments of an include or translation including the braces around them
+    fn field_values(&self, args: &Punctuated<syn::FieldValue, Token![,]>) -> String {
+        format!("{{ {} }}", tokens(args))
+    }
+
+    /// What is written between a `match` tag and its first arm, `indent` is the
+    /// whitespace at the start of the line the `match` is on and `arms` the whitespace
+    /// the other arms start their lines with, if they do
+    fn before_arms(&self, _indent: &str, _arms: Option<&str>) -> String {
+        String::new()
+    }
+}
+
+struct Tokens;
+
+impl PrintRust for Tokens {}
+
+// writes the source of nodes and remembers the line being written
+struct Printer<'a> {
+    f: &'a mut dyn Write,
+    delims: &'a Delims,
+    rust: &'a dyn PrintRust,
+    line: String,
+}
+
+impl Printer<'_> {
+    fn write(&mut self, s: &str) -> fmt::Result {
+        match s.rfind('\n') {
+            Some(i) => self.line = s[i + 1..].to_string(),
+            None => self.line.push_str(s),
+        }
+        self.f.write_str(s)
+    }
+
+    // write a single tag like `{% inner %}`
+    fn tag(&mut self, inner: &str) -> fmt::Result {
+        let tag = format!("{} {inner} {}", self.delims.open(), self.delims.close());
+        self.write(&tag)
+    }
+
+    fn end(&mut self) -> fmt::Result {
+        self.tag("end")
+    }
+
+    fn root(&mut self, root: &Root) -> fmt::Result {
+        self.items(&root.content)
+    }
+
+    fn items(&mut self, items: &[Item]) -> fmt::Result {
+        items.iter().try_for_each(|item| self.item(item))
+    }
+
+    // a string literal which the parser reads back as `value`
+    fn string(value: &str) -> String {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+
+    // the arguments of an include or translation, nothing if there are none
+    fn field_values(&self, args: &Punctuated<syn::FieldValue, Token![,]>) -> String {
+        match args.is_empty() {
+            true => String::new(),
+            false => format!(" {}", self.rust.field_values(args)),
+        }
+    }
+
+    fn item(&mut self, item: &Item) -> fmt::Result {
+        match item {
+            Item::Content(content) => self.write(content),
+            Item::Block(item) => self.block(item),
+            Item::For(item) => self.item_for(item),
+            Item::If(item) => self.item_if(item),
+            Item::Match(item) => self.item_match(item),
+            Item::Macro(item) => self.item_macro(item),
+            Item::Expr(expr) => self.expr(expr),
+        }
+    }
+
+    fn block(&mut self, item: &ItemBlock) -> fmt::Result {
+        self.tag(&format!("block {}", item.name))?;
+        self.items(&item.content)?;
+        self.end()
+    }
+
+    fn item_for(&mut self, item: &ItemFor) -> fmt::Result {
+        let label = match &item.label {
+            Some(label) => format!("{}: ", label.name),
+            None => String::new(),
+        };
+        let (pat, expr) = (self.rust.pat(&item.pat), self.rust.expr(&item.expr));
+        self.tag(&format!("{label}for {pat} in {expr}"))?;
+        self.items(&item.content)?;
+        self.end()
+    }
+
+    fn item_if(&mut self, item: &ItemIf) -> fmt::Result {
+        self.tag(&format!("if {}", self.rust.expr(&item.cond)))?;
+        self.items(&item.content)?;
+        self.if_branch(&item.branch)
+    }
+
+    fn if_branch(&mut self, branch: &IfBranch) -> fmt::Result {
+        match branch {
+            IfBranch::ElseIf {
+                cond,
+                content,
+                branch,
+            } => {
+                self.tag(&format!("else if {}", self.rust.expr(cond)))?;
+                self.items(content)?;
+                self.if_branch(branch)
+            }
+            IfBranch::Else { content } => {
+                self.tag("else")?;
+                self.items(content)?;
+                self.end()
+            }
+            IfBranch::End => self.end(),
+        }
+    }
+
+    fn item_match(&mut self, item: &ItemMatch) -> fmt::Result {
+        self.tag(&format!("match {}", self.rust.expr(&item.expr)))?;
+        // the parser drops the whitespace before the first arm
+        if !item.arms.is_empty() {
+            let indent = &self.line[..self.line.len() - self.line.trim_start().len()];
+            let before = self.rust.before_arms(indent, Self::arm_indent(item));
+            self.write(&before)?;
+        }
+        item.arms.iter().try_for_each(|arm| self.match_arm(arm))?;
+        self.end()
+    }
+
+    // the whitespace after the last line break before the second arm, if there is nothing else
+    fn arm_indent<'i>(item: &'i ItemMatch) -> Option<&'i str> {
+        item.arms.get(1)?;
+        let Some(Item::Content(content)) = item.arms[0].content.last() else {
+            return None;
+        };
+        let (_, indent) = content.rsplit_once('\n')?;
+        indent.trim().is_empty().then_some(indent)
+    }
+
+    fn match_arm(&mut self, arm: &MatchArm) -> fmt::Result {
+        let guard = match &arm.guard {
+            Some(guard) => format!(" if {}", self.rust.expr(guard)),
+            None => String::new(),
+        };
+        self.tag(&format!("when {}{guard}", self.rust.pat(&arm.pat)))?;
+        self.items(&arm.content)
+    }
+
+    fn item_macro(&mut self, item: &ItemMacro) -> fmt::Result {
+        let (name, args) = (&item.name, self.rust.fn_args(&item.args));
+        self.tag(&format!("macro {name}({args})"))?;
+        self.items(&item.content)?;
+        self.end()
+    }
+
+    fn expr(&mut self, expr: &Expr) -> fmt::Result {
+        let inner = match expr {
+            Expr::Extends(path) => format!("extends {}", Self::string(path)),
+            Expr::Include { reference, args } => {
+                let args = self.field_values(args);
+                format!("include {}{args}", Self::string(reference))
+            }
+            Expr::SuperCall => "super()".to_string(),
+            Expr::Translate { id, args } => {
+                format!("t {}{}", Self::string(id), self.field_values(args))
+            }
+            Expr::MacroCall { name, args } => {
+                let args = args.iter().map(|arg| self.rust.expr(arg));
+                format!("call {name}({})", args.collect::<Vec<_>>().join(", "))
+            }
+            Expr::Stmt(stmt) => self.rust.stmt(stmt),
+            Expr::Expr(expr) => self.rust.expr(expr),
+        };
+        self.tag(&inner)
+    }
+}
+
+macro_rules! impl_source {
+    ($($name:ident => $print:ident),*) => {
+        $(
+        impl $name<'_> {
+            /// Print this back into template source using some delimiters
+            pub fn to_source(&self, delims: &Delims) -> String {
+                self.to_source_with(delims, &Tokens)
+            }
+
+            /// Like `to_source` but the rust code in tags is printed with `rust`
+            pub fn to_source_with(&self, delims: &Delims, rust: &dyn PrintRust) -> String {
+                let mut out = String::new();
+                // writing to a string never fails
+                let _ = self.write_source(&mut out, delims, rust);
+                out
+            }
+
+            fn write_source(
+                &self,
+                f: &mut dyn Write,
+                delims: &Delims,
+                rust: &dyn PrintRust,
+            ) -> fmt::Result {
+                let mut printer = Printer {
+                    f,
+                    delims,
+                    rust,
+                    line: String::new(),
+                };
+                printer.$print(self)
+            }
+        }
+
+        /// Prints template source with the default delimiters
+        impl fmt::Display for $name<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.write_source(f, &Delims::default(), &Tokens)
+            }
+        }
+        )*
+    };
+}
+
+impl_source!(
+    Root => root,
+    Item => item,
+    ItemBlock => block,
+    ItemFor => item_for,
+    ItemIf => item_if,
+    IfBranch => if_branch,
+    ItemMatch => item_match,
+    MatchArm => match_arm,
+    ItemMacro => item_macro,
+    Expr => expr
+);
@@ -0,0 +1,106 @@
+//! Serde helpers for the rust code inside of the syntax tree
+//!
+//! Rust code is (de)serialized as its source, a `Punctuated` as a list of sources.
+
+use quote::ToTokens;
+use serde::de::Error as _;
+use serde::ser::SerializeSeq;
+use serde::{Deserialize, Deserializer, Serializer};
+use syn::parse::{Parse, Parser};
+
+fn source(node: &impl ToTokens) -> String {
+    node.to_token_stream().to_string()
+}
+
+fn parse<'de, D, T>(deserializer: D, parser: impl Parser<Output = T>) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let source = String::deserialize(deserializer)?;
+    parser.parse_str(&source).map_err(D::Error::custom)
+}
+
+pub mod tokens {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(node: &impl ToTokens, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&source(node))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, T: Parse>(d: D) -> Result<T, D::Error> {
+        parse(d, T::parse)
+    }
+}
+
+pub mod option {
+    use super::*;
+
+    pub fn serialize<S, T>(node: &Option<T>, s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: ToTokens,
+    {
+        match node {
+            Some(node) => s.serialize_some(&source(node)),
+            None => s.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, T: Parse>(d: D) -> Result<Option<T>, D::Error> {
+        Option::<String>::deserialize(d)?
+            .map(|source| syn::parse_str(&source).map_err(D::Error::custom))
+            .transpose()
+    }
+}
+
+// the pattern of a `for` loop, which can not have a top level `|`
+pub mod pat_single {
+    use super::*;
+
+    pub use super::tokens::serialize;
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<syn::Pat, D::Error> {
+        parse(d, syn::Pat::parse_single)
+    }
+}
+
+// the pattern of a match arm, like `Some(1) | None`
+pub mod pat_multi {
+    use super::*;
+
+    pub use super::tokens::serialize;
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<syn::Pat, D::Error> {
+        parse(d, syn::Pat::parse_multi)
+    }
+}
+
+pub mod punctuated {
+    use syn::punctuated::Punctuated;
+
+    use super::*;
+
+    pub fn serialize<S, T, P>(nodes: &Punctuated<T, P>, s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: ToTokens,
+    {
+        let mut seq = s.serialize_seq(Some(nodes.len()))?;
+        for node in nodes {
+            seq.serialize_element(&source(node))?;
+        }
+        seq.end()
+    }
+
+    pub fn deserialize<'de, D, T, P>(d: D) -> Result<Punctuated<T, P>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Parse,
+        P: Default,
+    {
+        Vec::<String>::deserialize(d)?
+            .iter()
+            .map(|source| syn::parse_str::<T>(source).map_err(D::Error::custom))
+            .collect()
+    }
+}
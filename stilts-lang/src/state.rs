@@ -2,6 +2,8 @@ use std::borrow::Cow;
 
 use bitflags::bitflags;
 
+use crate::Error;
+
 type Lrc<T> = std::rc::Rc<T>;
 
 /// Template Delimiters
@@ -51,6 +53,31 @@ bitflags! {
     }
 }
 
+/// What the parser keeps track of next to the input
+#[derive(Debug, Default)]
+pub struct Context<'i> {
+    pub flags: State,
+    /// The errors which were recovered from, only collected when recovering
+    pub errors: Option<Vec<Error<'i>>>,
+}
+
+impl<'i> Context<'i> {
+    pub fn recovering(&self) -> bool {
+        self.errors.is_some()
+    }
+
+    /// Keep an error and carry on parsing, returns false when not recovering
+    pub fn recover(&mut self, err: Error<'i>) -> bool {
+        match &mut self.errors {
+            Some(errors) => {
+                errors.push(err);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
 impl Default for State {
     fn default() -> Self {
         Self::ALLOW_BLOCK | Self::ALLOW_EXTEND
@@ -4,6 +4,7 @@ use std::borrow::Cow;
 ///
 /// It contains all the content of the template
 #[cfg_attr(any(test, feature = "extra-traits"), derive(Clone, Debug, PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Root<'i> {
     pub content: Vec<Item<'i>>,
 }
@@ -14,6 +15,8 @@ pub struct Root<'i> {
 /// of the template. An item is either template content, multi-expression block, or a single
 /// expression
 #[cfg_attr(any(test, feature = "extra-traits"), derive(Clone, Debug, PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value", rename_all = "snake_case"))]
 pub enum Item<'i> {
     Content(Cow<'i, str>),
     Block(ItemBlock<'i>),
@@ -25,29 +28,39 @@ pub enum Item<'i> {
 }
 
 #[cfg_attr(any(test, feature = "extra-traits"), derive(Clone, Debug, PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ItemBlock<'i> {
     pub name: Cow<'i, str>,
     pub content: Vec<Item<'i>>,
 }
 
 #[cfg_attr(any(test, feature = "extra-traits"), derive(Clone, Debug, PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ItemFor<'i> {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_syn::option"))]
     pub label: Option<syn::Label>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_syn::pat_single"))]
     pub pat: syn::Pat,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_syn::tokens"))]
     pub expr: syn::Expr,
     pub content: Vec<Item<'i>>,
 }
 
 #[cfg_attr(any(test, feature = "extra-traits"), derive(Clone, Debug, PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ItemIf<'i> {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_syn::tokens"))]
     pub cond: syn::Expr,
     pub content: Vec<Item<'i>>,
     pub branch: IfBranch<'i>,
 }
 
 #[cfg_attr(any(test, feature = "extra-traits"), derive(Clone, Debug, PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value", rename_all = "snake_case"))]
 pub enum IfBranch<'i> {
     ElseIf {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_syn::tokens"))]
         cond: syn::Expr,
         content: Vec<Item<'i>>,
         branch: Box<IfBranch<'i>>,
@@ -59,38 +72,58 @@ pub enum IfBranch<'i> {
 }
 
 #[cfg_attr(any(test, feature = "extra-traits"), derive(Clone, Debug, PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ItemMatch<'i> {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_syn::tokens"))]
     pub expr: syn::Expr,
     pub arms: Vec<MatchArm<'i>>,
 }
 
 #[cfg_attr(any(test, feature = "extra-traits"), derive(Clone, Debug, PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MatchArm<'i> {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_syn::pat_multi"))]
     pub pat: syn::Pat,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_syn::option"))]
     pub guard: Option<syn::Expr>,
     pub content: Vec<Item<'i>>,
 }
 
 #[cfg_attr(any(test, feature = "extra-traits"), derive(Clone, Debug, PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ItemMacro<'i> {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_syn::tokens"))]
     pub name: syn::Ident,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_syn::punctuated"))]
     pub args: syn::punctuated::Punctuated<syn::FnArg, syn::Token![,]>,
     pub content: Vec<Item<'i>>,
 }
 
 #[cfg_attr(any(test, feature = "extra-traits"), derive(Clone, Debug, PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value", rename_all = "snake_case"))]
 pub enum Expr<'i> {
     Extends(Cow<'i, str>),
     Include {
         reference: Cow<'i, str>,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_syn::punctuated"))]
         args: syn::punctuated::Punctuated<syn::FieldValue, syn::Token![,]>,
     },
     SuperCall,
+    Translate {
+        id: Cow<'i, str>,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_syn::punctuated"))]
+        args: syn::punctuated::Punctuated<syn::FieldValue, syn::Token![,]>,
+    },
     MacroCall {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_syn::tokens"))]
         name: syn::Ident,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_syn::punctuated"))]
         args: syn::punctuated::Punctuated<syn::Expr, syn::Token![,]>,
     },
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_syn::tokens"))]
     Stmt(syn::Stmt),
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_syn::tokens"))]
     Expr(syn::Expr),
 }
 
@@ -164,6 +197,10 @@ impl<'i> Expr<'i> {
                 args,
             },
             Self::SuperCall => Expr::SuperCall,
+            Self::Translate { id, args } => Expr::Translate {
+                id: id.into_owned().into(),
+                args,
+            },
             Self::MacroCall { name, args } => Expr::MacroCall { name, args },
             Self::Stmt(v) => Expr::Stmt(v),
             Self::Expr(v) => Expr::Expr(v),
@@ -0,0 +1,192 @@
+//! Walk the syntax tree of a template by reference
+//!
+//! Each method of [`Visit`] is called for one type of node, by default it calls the function
+//! of the same name in this module which visits the children of the node. Implement only the
+//! methods for the nodes you are interested in and call the function from them to keep
+//! descending. The rust code inside of tags is not walked, use `syn::visit` for that.
+//!
+//! ```
+//! use stilts_lang::types::ItemBlock;
+//! use stilts_lang::visit::{self, Visit};
+//!
+//! struct BlockNames(Vec<String>);
+//!
+//! impl<'ast, 'i> Visit<'ast, 'i> for BlockNames {
+//!     fn visit_item_block(&mut self, block: &'ast ItemBlock<'i>) {
+//!         self.0.push(block.name.to_string());
+//!         visit::visit_item_block(self, block);
+//!     }
+//! }
+//!
+//! let root = stilts_lang::parse_template(
+//!     "{% block a %}{% if x %}{% block b %}{% end %}{% end %}{% end %}",
+//!     Default::default(),
+//! ).unwrap();
+//! let mut names = BlockNames(Vec::new());
+//! names.visit_root(&root);
+//! assert_eq!(names.0, ["a", "b"]);
+//! ```
+
+use crate::types::{
+    Expr, IfBranch, Item, ItemBlock, ItemFor, ItemIf, ItemMacro, ItemMatch, MatchArm, Root,
+};
+
+/// Visit the nodes of a template by reference
+pub trait Visit<'ast, 'i> {
+    fn visit_root(&mut self, node: &'ast Root<'i>) {
+        visit_root(self, node);
+    }
+
+    fn visit_item(&mut self, node: &'ast Item<'i>) {
+        visit_item(self, node);
+    }
+
+    fn visit_content(&mut self, node: &'ast str) {
+        visit_content(self, node);
+    }
+
+    fn visit_item_block(&mut self, node: &'ast ItemBlock<'i>) {
+        visit_item_block(self, node);
+    }
+
+    fn visit_item_for(&mut self, node: &'ast ItemFor<'i>) {
+        visit_item_for(self, node);
+    }
+
+    fn visit_item_if(&mut self, node: &'ast ItemIf<'i>) {
+        visit_item_if(self, node);
+    }
+
+    fn visit_if_branch(&mut self, node: &'ast IfBranch<'i>) {
+        visit_if_branch(self, node);
+    }
+
+    fn visit_item_match(&mut self, node: &'ast ItemMatch<'i>) {
+        visit_item_match(self, node);
+    }
+
+    fn visit_match_arm(&mut self, node: &'ast MatchArm<'i>) {
+        visit_match_arm(self, node);
+    }
+
+    fn visit_item_macro(&mut self, node: &'ast ItemMacro<'i>) {
+        visit_item_macro(self, node);
+    }
+
+    fn visit_expr(&mut self, node: &'ast Expr<'i>) {
+        visit_expr(self, node);
+    }
+}
+
+pub fn visit_root<'ast, 'i, V>(v: &mut V, node: &'ast Root<'i>)
+where
+    V: Visit<'ast, 'i> + ?Sized,
+{
+    for item in &node.content {
+        v.visit_item(item);
+    }
+}
+
+pub fn visit_item<'ast, 'i, V>(v: &mut V, node: &'ast Item<'i>)
+where
+    V: Visit<'ast, 'i> + ?Sized,
+{
+    match node {
+        Item::Content(content) => v.visit_content(content),
+        Item::Block(block) => v.visit_item_block(block),
+        Item::For(item) => v.visit_item_for(item),
+        Item::If(item) => v.visit_item_if(item),
+        Item::Match(item) => v.visit_item_match(item),
+        Item::Macro(item) => v.visit_item_macro(item),
+        Item::Expr(expr) => v.visit_expr(expr),
+    }
+}
+
+pub fn visit_content<'ast, 'i, V>(_v: &mut V, _node: &'ast str)
+where
+    V: Visit<'ast, 'i> + ?Sized,
+{
+}
+
+pub fn visit_item_block<'ast, 'i, V>(v: &mut V, node: &'ast ItemBlock<'i>)
+where
+    V: Visit<'ast, 'i> + ?Sized,
+{
+    for item in &node.content {
+        v.visit_item(item);
+    }
+}
+
+pub fn visit_item_for<'ast, 'i, V>(v: &mut V, node: &'ast ItemFor<'i>)
+where
+    V: Visit<'ast, 'i> + ?Sized,
+{
+    for item in &node.content {
+        v.visit_item(item);
+    }
+}
+
+pub fn visit_item_if<'ast, 'i, V>(v: &mut V, node: &'ast ItemIf<'i>)
+where
+    V: Visit<'ast, 'i> + ?Sized,
+{
+    for item in &node.content {
+        v.visit_item(item);
+    }
+    v.visit_if_branch(&node.branch);
+}
+
+pub fn visit_if_branch<'ast, 'i, V>(v: &mut V, node: &'ast IfBranch<'i>)
+where
+    V: Visit<'ast, 'i> + ?Sized,
+{
+    match node {
+        IfBranch::ElseIf {
+            content, branch, ..
+        } => {
+            for item in content {
+                v.visit_item(item);
+            }
+            v.visit_if_branch(branch);
+        }
+        IfBranch::Else { content } => {
+            for item in content {
+                v.visit_item(item);
+            }
+        }
+        IfBranch::End => {}
+    }
+}
+
+pub fn visit_item_match<'ast, 'i, V>(v: &mut V, node: &'ast ItemMatch<'i>)
+where
+    V: Visit<'ast, 'i> + ?Sized,
+{
+    for arm in &node.arms {
+        v.visit_match_arm(arm);
+    }
+}
+
+pub fn visit_match_arm<'ast, 'i, V>(v: &mut V, node: &'ast MatchArm<'i>)
+where
+    V: Visit<'ast, 'i> + ?Sized,
+{
+    for item in &node.content {
+        v.visit_item(item);
+    }
+}
+
+pub fn visit_item_macro<'ast, 'i, V>(v: &mut V, node: &'ast ItemMacro<'i>)
+where
+    V: Visit<'ast, 'i> + ?Sized,
+{
+    for item in &node.content {
+        v.visit_item(item);
+    }
+}
+
+pub fn visit_expr<'ast, 'i, V>(_v: &mut V, _node: &'ast Expr<'i>)
+where
+    V: Visit<'ast, 'i> + ?Sized,
+{
+}
@@ -0,0 +1,191 @@
+//! Walk the syntax tree of a template by mutable reference
+//!
+//! This works like [`visit`](crate::visit) except every node can be changed in place.
+//!
+//! ```
+//! use std::borrow::Cow;
+//! use stilts_lang::types::Item;
+//! use stilts_lang::visit_mut::VisitMut;
+//!
+//! // collapse the whitespace in the content of a template
+//! struct Collapse;
+//!
+//! impl<'i> VisitMut<'i> for Collapse {
+//!     fn visit_content_mut(&mut self, node: &mut Cow<'i, str>) {
+//!         *node = node.split_whitespace().collect::<Vec<_>>().join(" ").into();
+//!     }
+//! }
+//!
+//! let mut root = stilts_lang::parse_template(
+//!     "<p>\n    {% for x in y %}\n  {% x %}\n{% end %}</p>",
+//!     Default::default(),
+//! ).unwrap();
+//! Collapse.visit_root_mut(&mut root);
+//! assert!(matches!(&root.content[0], Item::Content(content) if content == "<p>"));
+//! ```
+
+use std::borrow::Cow;
+
+use crate::types::{
+    Expr, IfBranch, Item, ItemBlock, ItemFor, ItemIf, ItemMacro, ItemMatch, MatchArm, Root,
+};
+
+/// Visit the nodes of a template by mutable reference
+pub trait VisitMut<'i> {
+    fn visit_root_mut(&mut self, node: &mut Root<'i>) {
+        visit_root_mut(self, node);
+    }
+
+    fn visit_item_mut(&mut self, node: &mut Item<'i>) {
+        visit_item_mut(self, node);
+    }
+
+    fn visit_content_mut(&mut self, node: &mut Cow<'i, str>) {
+        visit_content_mut(self, node);
+    }
+
+    fn visit_item_block_mut(&mut self, node: &mut ItemBlock<'i>) {
+        visit_item_block_mut(self, node);
+    }
+
+    fn visit_item_for_mut(&mut self, node: &mut ItemFor<'i>) {
+        visit_item_for_mut(self, node);
+    }
+
+    fn visit_item_if_mut(&mut self, node: &mut ItemIf<'i>) {
+        visit_item_if_mut(self, node);
+    }
+
+    fn visit_if_branch_mut(&mut self, node: &mut IfBranch<'i>) {
+        visit_if_branch_mut(self, node);
+    }
+
+    fn visit_item_match_mut(&mut self, node: &mut ItemMatch<'i>) {
+        visit_item_match_mut(self, node);
+    }
+
+    fn visit_match_arm_mut(&mut self, node: &mut MatchArm<'i>) {
+        visit_match_arm_mut(self, node);
+    }
+
+    fn visit_item_macro_mut(&mut self, node: &mut ItemMacro<'i>) {
+        visit_item_macro_mut(self, node);
+    }
+
+    fn visit_expr_mut(&mut self, node: &mut Expr<'i>) {
+        visit_expr_mut(self, node);
+    }
+}
+
+pub fn visit_root_mut<'i, V>(v: &mut V, node: &mut Root<'i>)
+where
+    V: VisitMut<'i> + ?Sized,
+{
+    for item in &mut node.content {
+        v.visit_item_mut(item);
+    }
+}
+
+pub fn visit_item_mut<'i, V>(v: &mut V, node: &mut Item<'i>)
+where
+    V: VisitMut<'i> + ?Sized,
+{
+    match node {
+        Item::Content(content) => v.visit_content_mut(content),
+        Item::Block(block) => v.visit_item_block_mut(block),
+        Item::For(item) => v.visit_item_for_mut(item),
+        Item::If(item) => v.visit_item_if_mut(item),
+        Item::Match(item) => v.visit_item_match_mut(item),
+        Item::Macro(item) => v.visit_item_macro_mut(item),
+        Item::Expr(expr) => v.visit_expr_mut(expr),
+    }
+}
+
+pub fn visit_content_mut<'i, V>(_v: &mut V, _node: &mut Cow<'i, str>)
+where
+    V: VisitMut<'i> + ?Sized,
+{
+}
+
+pub fn visit_item_block_mut<'i, V>(v: &mut V, node: &mut ItemBlock<'i>)
+where
+    V: VisitMut<'i> + ?Sized,
+{
+    for item in &mut node.content {
+        v.visit_item_mut(item);
+    }
+}
+
+pub fn visit_item_for_mut<'i, V>(v: &mut V, node: &mut ItemFor<'i>)
+where
+    V: VisitMut<'i> + ?Sized,
+{
+    for item in &mut node.content {
+        v.visit_item_mut(item);
+    }
+}
+
+pub fn visit_item_if_mut<'i, V>(v: &mut V, node: &mut ItemIf<'i>)
+where
+    V: VisitMut<'i> + ?Sized,
+{
+    for item in &mut node.content {
+        v.visit_item_mut(item);
+    }
+    v.visit_if_branch_mut(&mut node.branch);
+}
+
+pub fn visit_if_branch_mut<'i, V>(v: &mut V, node: &mut IfBranch<'i>)
+where
+    V: VisitMut<'i> + ?Sized,
+{
+    match node {
+        IfBranch::ElseIf {
+            content, branch, ..
+        } => {
+            for item in content {
+                v.visit_item_mut(item);
+            }
+            v.visit_if_branch_mut(branch);
+        }
+        IfBranch::Else { content } => {
+            for item in content {
+                v.visit_item_mut(item);
+            }
+        }
+        IfBranch::End => {}
+    }
+}
+
+pub fn visit_item_match_mut<'i, V>(v: &mut V, node: &mut ItemMatch<'i>)
+where
+    V: VisitMut<'i> + ?Sized,
+{
+    for arm in &mut node.arms {
+        v.visit_match_arm_mut(arm);
+    }
+}
+
+pub fn visit_match_arm_mut<'i, V>(v: &mut V, node: &mut MatchArm<'i>)
+where
+    V: VisitMut<'i> + ?Sized,
+{
+    for item in &mut node.content {
+        v.visit_item_mut(item);
+    }
+}
+
+pub fn visit_item_macro_mut<'i, V>(v: &mut V, node: &mut ItemMacro<'i>)
+where
+    V: VisitMut<'i> + ?Sized,
+{
+    for item in &mut node.content {
+        v.visit_item_mut(item);
+    }
+}
+
+pub fn visit_expr_mut<'i, V>(_v: &mut V, _node: &mut Expr<'i>)
+where
+    V: VisitMut<'i> + ?Sized,
+{
+}
@@ -0,0 +1,154 @@
+use std::ops::Range;
+
+use stilts_lang::lex::{lex, Token, TokenKind};
+use stilts_lang::Delims;
+use tower_lsp::lsp_types::Position;
+
+/// A tag in a template and where it is in the source
+pub struct Tag<'a> {
+    pub span: Range<usize>,
+    pub kind: TagKind<'a>,
+}
+
+pub enum TagKind<'a> {
+    Extends(Name<'a>),
+    Include(Name<'a>),
+    Block(Name<'a>),
+    Macro(Name<'a>),
+    // a `for`, `if` or `match` which is closed by an `end`
+    Open,
+    End,
+    Other,
+}
+
+/// A path or name inside of a tag
+pub struct Name<'a> {
+    pub value: &'a str,
+    pub span: Range<usize>,
+}
+
+impl Tag<'_> {
+    pub fn contains(&self, offset: usize) -> bool {
+        self.span.start <= offset && offset <= self.span.end
+    }
+}
+
+// find the tags of a template with the lexer, the parser has no spans
+// so this is how positions in the source are found
+pub fn tags<'a>(source: &'a str, delims: &Delims) -> Vec<Tag<'a>> {
+    let mut tags = Vec::new();
+    let mut tokens = lex(source, delims).into_iter();
+    while let Some(open) = tokens.next() {
+        if open.kind != TokenKind::Open {
+            continue;
+        }
+        // the lexer always closes a tag it opened
+        let mut inner = Vec::new();
+        let mut end = open.span.end;
+        for token in tokens.by_ref() {
+            if token.kind == TokenKind::Close {
+                end = token.span.end;
+                break;
+            }
+            inner.push(token);
+        }
+
+        let name = |kind| match inner.get(1) {
+            Some(token) if token.kind == kind => Some(name(token)),
+            _ => None,
+        };
+        let kind = match inner.first() {
+            Some(Token { kind: TokenKind::Keyword, text, .. }) => match *text {
+                "extends" => name(TokenKind::Path).map(TagKind::Extends),
+                "include" => name(TokenKind::Path).map(TagKind::Include),
+                "block" => name(TokenKind::Name).map(TagKind::Block),
+                "macro" => name(TokenKind::Name).map(TagKind::Macro),
+                "for" | "if" | "match" => Some(TagKind::Open),
+                "end" => Some(TagKind::End),
+                _ => None,
+            },
+            _ => None,
+        };
+        tags.push(Tag {
+            span: open.span.start..end,
+            kind: kind.unwrap_or(TagKind::Other),
+        });
+    }
+    tags
+}
+
+// a name from the lexer, paths are string literals so the quotes are left out
+fn name<'a>(token: &Token<'a>) -> Name<'a> {
+    let (value, span) = match token.kind {
+        TokenKind::Path => (
+            &token.text[1..token.text.len() - 1],
+            token.span.start + 1..token.span.end - 1,
+        ),
+        _ => (token.text, token.span.clone()),
+    };
+    Name { value, span }
+}
+
+// language server positions count utf-16 code units on each line
+pub fn position(source: &str, offset: usize) -> Position {
+    let offset = offset.min(source.len());
+    let before = &source[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    Position {
+        line: before.matches('\n').count() as u32,
+        character: before[line_start..].encode_utf16().count() as u32,
+    }
+}
+
+pub fn offset(source: &str, position: Position) -> usize {
+    let line_start = match position.line {
+        0 => 0,
+        line => source
+            .match_indices('\n')
+            .nth(line as usize - 1)
+            .map_or(source.len(), |(i, _)| i + 1),
+    };
+    let mut units = 0;
+    for (i, c) in source[line_start..].char_indices() {
+        if units >= position.character as usize || c == '\n' {
+            return line_start + i;
+        }
+        units += c.len_utf16();
+    }
+    source.len()
+}
+
+pub fn range(source: &str, span: Range<usize>) -> tower_lsp::lsp_types::Range {
+    tower_lsp::lsp_types::Range {
+        start: position(source, span.start),
+        end: position(source, span.end),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{offset, position};
+    use tower_lsp::lsp_types::Position;
+
+    #[test]
+    fn utf16_positions() {
+        let source = "a\né😀b\n\nc";
+        let positions = [
+            (0, Position::new(0, 0)),
+            (2, Position::new(1, 0)),
+            (4, Position::new(1, 1)),
+            (8, Position::new(1, 3)),
+            (9, Position::new(1, 4)),
+            (11, Position::new(3, 0)),
+        ];
+        for (at, pos) in positions {
+            assert_eq!(position(source, at), pos);
+            assert_eq!(offset(source, pos), at);
+        }
+
+        // positions past the end of a line or the source are clamped
+        assert_eq!(offset(source, Position::new(0, 5)), 1);
+        assert_eq!(offset(source, Position::new(7, 0)), source.len());
+        assert_eq!(position(source, 100), Position::new(3, 1));
+    }
+}
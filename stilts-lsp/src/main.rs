@@ -0,0 +1,424 @@
+//! A language server for stilts templates
+//!
+//! The server speaks the language server protocol over stdin and stdout. It publishes
+//! the errors of a template as it is edited, goes to the templates of `extends` and `include`
+//! and to the blocks a block overrides, completes the names of blocks from the templates being
+//! extended and lists the blocks and macros of a template as its outline.
+//!
+//! Templates are found and parsed with the config in the `Cargo.toml` closest to them, which
+//! is loaded again whenever that manifest changes.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use tower_lsp::jsonrpc::Result;
+use tower_lsp::lsp_types::*;
+use tower_lsp::{Client, LanguageServer, LspService, Server};
+
+use document::{offset, range, tags, TagKind};
+use project::{Project, Projects};
+
+mod document;
+mod project;
+
+struct Backend {
+    client: Client,
+    documents: Mutex<HashMap<Url, String>>,
+    projects: Projects,
+}
+
+impl Backend {
+    fn source(&self, uri: &Url) -> Option<String> {
+        self.documents.lock().unwrap().get(uri).cloned()
+    }
+
+    // open documents may have changes which are not saved yet
+    fn read(&self, path: &Path) -> Option<String> {
+        Url::from_file_path(path)
+            .ok()
+            .and_then(|uri| self.source(&uri))
+            .or_else(|| std::fs::read_to_string(path).ok())
+    }
+
+    fn project(&self, uri: &Url) -> Option<Arc<Project>> {
+        let path = uri.to_file_path().ok()?;
+        Some(self.projects.find(&path))
+    }
+
+    async fn update(&self, uri: Url, source: String, version: Option<i32>) {
+        let diagnostics = match self.project(&uri) {
+            Some(project) => diagnostics(&project, &source),
+            None => Vec::new(),
+        };
+        self.documents.lock().unwrap().insert(uri.clone(), source);
+        self.client
+            .publish_diagnostics(uri, diagnostics, version)
+            .await;
+    }
+
+    fn definition(&self, uri: &Url, position: Position) -> Option<Location> {
+        let project = self.project(uri)?;
+        let source = self.source(uri)?;
+        let offset = offset(&source, position);
+        let tag = tags(&source, &project.delims())
+            .into_iter()
+            .find(|tag| tag.contains(offset))?;
+
+        match tag.kind {
+            TagKind::Extends(name) | TagKind::Include(name) => {
+                let path = project.resolve(name.value);
+                path.is_file().then_some(())?;
+                Some(Location::new(
+                    Url::from_file_path(path).ok()?,
+                    Range::default(),
+                ))
+            }
+            // the closest template above this one with the same block
+            TagKind::Block(name) => project
+                .ancestors(&source, |path| self.read(path))
+                .into_iter()
+                .find_map(|(path, parent)| {
+                    let block = tags(&parent, &project.delims())
+                        .into_iter()
+                        .find_map(|tag| match tag.kind {
+                            TagKind::Block(block) if block.value == name.value => Some(block),
+                            _ => None,
+                        })?;
+                    let uri = Url::from_file_path(path).ok()?;
+                    Some(Location::new(uri, range(&parent, block.span)))
+                }),
+            _ => None,
+        }
+    }
+
+    fn completion(&self, uri: &Url, position: Position) -> Option<Vec<CompletionItem>> {
+        let project = self.project(uri)?;
+        let source = self.source(uri)?;
+        let offset = offset(&source, position);
+        completion(&project, &source, offset, |path| self.read(path))
+    }
+
+    fn symbols(&self, uri: &Url) -> Option<Vec<DocumentSymbol>> {
+        let project = self.project(uri)?;
+        let source = self.source(uri)?;
+        Some(symbols(&project, &source))
+    }
+}
+
+// the errors of a template and the errors related to them
+fn diagnostics(project: &Project, source: &str) -> Vec<Diagnostic> {
+    let (_, errors) = stilts_lang::parse_template_recovering(source, project.delims());
+    let mut diagnostics = Vec::new();
+    let mut errors = errors
+        .iter()
+        .rev()
+        .map(|err| err as &dyn miette::Diagnostic)
+        .collect::<Vec<_>>();
+    while let Some(err) = errors.pop() {
+        let span = err
+            .labels()
+            .and_then(|mut labels| labels.next())
+            .map(|label| label.offset()..label.offset() + label.len())
+            .unwrap_or(0..0);
+        diagnostics.push(Diagnostic {
+            range: range(source, span),
+            severity: Some(DiagnosticSeverity::ERROR),
+            source: Some("stilts".to_string()),
+            message: err.to_string(),
+            ..Default::default()
+        });
+        if let Some(related) = err.related() {
+            errors.extend(related);
+        }
+    }
+    diagnostics
+}
+
+// the names of the blocks in the templates being extended when `offset` is in an unclosed
+// `block` tag, `read` gets the source of a template like for `Project::ancestors`
+fn completion(
+    project: &Project,
+    source: &str,
+    offset: usize,
+    read: impl Fn(&Path) -> Option<String>,
+) -> Option<Vec<CompletionItem>> {
+    let before = &source[..offset];
+    let inner = &before[before.rfind(project.open())? + project.open().len()..];
+    if inner.contains(project.close()) {
+        return None;
+    }
+    let name = inner.trim_start().strip_prefix("block")?;
+    if !name.starts_with(char::is_whitespace) || name.trim_start().contains(' ') {
+        return None;
+    }
+
+    let mut items = Vec::<CompletionItem>::new();
+    for (path, parent) in project.ancestors(source, read) {
+        let template = path.strip_prefix(&project.template_dir).unwrap_or(&path);
+        for tag in tags(&parent, &project.delims()) {
+            let TagKind::Block(block) = tag.kind else {
+                continue;
+            };
+            if items.iter().any(|item| item.label == block.value) {
+                continue;
+            }
+            items.push(CompletionItem {
+                label: block.value.to_string(),
+                kind: Some(CompletionItemKind::MODULE),
+                detail: Some(template.display().to_string()),
+                ..Default::default()
+            });
+        }
+    }
+    Some(items)
+}
+
+// a block or macro which has not seen its `end` yet
+struct Frame {
+    symbol: Option<(String, SymbolKind, usize, Range)>,
+    children: Vec<DocumentSymbol>,
+}
+
+impl Frame {
+    fn close(self, source: &str, end: usize, parent: &mut Frame) {
+        let Some((name, kind, start, selection_range)) = self.symbol else {
+            // symbols inside of loops and conditions belong to the enclosing symbol
+            parent.children.extend(self.children);
+            return;
+        };
+        #[allow(deprecated)]
+        parent.children.push(DocumentSymbol {
+            name,
+            detail: None,
+            kind,
+            tags: None,
+            deprecated: None,
+            range: range(source, start..end),
+            selection_range,
+            children: Some(self.children),
+        });
+    }
+}
+
+fn symbols(project: &Project, source: &str) -> Vec<DocumentSymbol> {
+    let frame = |symbol| Frame {
+        symbol,
+        children: Vec::new(),
+    };
+    let mut stack = vec![frame(None)];
+    for tag in tags(source, &project.delims()) {
+        let symbol = |name: document::Name, kind| {
+            let selection_range = range(source, name.span);
+            Some((
+                name.value.to_string(),
+                kind,
+                tag.span.start,
+                selection_range,
+            ))
+        };
+        match tag.kind {
+            TagKind::Block(name) => stack.push(frame(symbol(name, SymbolKind::MODULE))),
+            TagKind::Macro(name) => stack.push(frame(symbol(name, SymbolKind::FUNCTION))),
+            TagKind::Open => stack.push(frame(None)),
+            TagKind::End if stack.len() > 1 => {
+                let top = stack.pop().unwrap();
+                top.close(source, tag.span.end, stack.last_mut().unwrap());
+            }
+            _ => {}
+        }
+    }
+    // anything left open runs to the end of the template
+    while stack.len() > 1 {
+        let top = stack.pop().unwrap();
+        top.close(source, source.len(), stack.last_mut().unwrap());
+    }
+    stack.pop().unwrap().children
+}
+
+#[tower_lsp::async_trait]
+impl LanguageServer for Backend {
+    async fn initialize(&self, _: InitializeParams) -> Result<InitializeResult> {
+        Ok(InitializeResult {
+            capabilities: ServerCapabilities {
+                text_document_sync: Some(TextDocumentSyncCapability::Kind(
+                    TextDocumentSyncKind::FULL,
+                )),
+                definition_provider: Some(OneOf::Left(true)),
+                completion_provider: Some(CompletionOptions {
+                    trigger_characters: Some(vec![" ".to_string()]),
+                    ..Default::default()
+                }),
+                document_symbol_provider: Some(OneOf::Left(true)),
+                ..Default::default()
+            },
+            server_info: Some(ServerInfo {
+                name: env!("CARGO_PKG_NAME").to_string(),
+                version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            }),
+        })
+    }
+
+    async fn shutdown(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn did_open(&self, params: DidOpenTextDocumentParams) {
+        let doc = params.text_document;
+        self.update(doc.uri, doc.text, Some(doc.version)).await;
+    }
+
+    async fn did_change(&self, mut params: DidChangeTextDocumentParams) {
+        // full sync sends the whole document as the only change
+        let Some(change) = params.content_changes.pop() else {
+            return;
+        };
+        let doc = params.text_document;
+        self.update(doc.uri, change.text, Some(doc.version)).await;
+    }
+
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        let uri = params.text_document.uri;
+        self.documents.lock().unwrap().remove(&uri);
+        self.client.publish_diagnostics(uri, Vec::new(), None).await;
+    }
+
+    async fn goto_definition(
+        &self,
+        params: GotoDefinitionParams,
+    ) -> Result<Option<GotoDefinitionResponse>> {
+        let params = params.text_document_position_params;
+        let location = self.definition(&params.text_document.uri, params.position);
+        Ok(location.map(GotoDefinitionResponse::Scalar))
+    }
+
+    async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
+        let params = params.text_document_position;
+        let items = self.completion(&params.text_document.uri, params.position);
+        Ok(items.map(CompletionResponse::Array))
+    }
+
+    async fn document_symbol(
+        &self,
+        params: DocumentSymbolParams,
+    ) -> Result<Option<DocumentSymbolResponse>> {
+        let symbols = self.symbols(&params.text_document.uri);
+        Ok(symbols.map(DocumentSymbolResponse::Nested))
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let (service, socket) = LspService::new(|client| Backend {
+        client,
+        documents: Mutex::new(HashMap::new()),
+        projects: Projects::default(),
+    });
+    Server::new(tokio::io::stdin(), tokio::io::stdout(), socket)
+        .serve(service)
+        .await;
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use tower_lsp::lsp_types::{Position, Range, SymbolKind};
+
+    use super::{completion, diagnostics, symbols};
+    use crate::project::Project;
+
+    fn project() -> Project {
+        Project::fallback(Path::new("/templates/page.html"))
+    }
+
+    #[test]
+    fn diagnostics_have_ranges() {
+        let source = "<p>\n{% if %}</p>";
+        assert!(diagnostics(&project(), "{% if a %}{% end %}").is_empty());
+        let found = diagnostics(&project(), source)
+            .into_iter()
+            .map(|d| (d.range, d.message))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            [
+                (
+                    Range::new(Position::new(1, 6), Position::new(1, 6)),
+                    "unexpected end of input, expected an expression".to_string()
+                ),
+                (
+                    Range::new(Position::new(1, 0), Position::new(1, 8)),
+                    "expected closing {% end %} expression".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn symbols_are_nested() {
+        let source = "{% block a %}{% macro m() %}{% end %}\n\
+            {% if x %}{% block b %}{% end %}{% end %}{% end %}{% block c %}";
+        let symbols = symbols(&project(), source);
+        let names = |symbols: &[tower_lsp::lsp_types::DocumentSymbol]| {
+            symbols
+                .iter()
+                .map(|s| (s.name.clone(), s.kind))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            names(&symbols),
+            [
+                ("a".to_string(), SymbolKind::MODULE),
+                ("c".to_string(), SymbolKind::MODULE)
+            ]
+        );
+        assert_eq!(
+            names(symbols[0].children.as_deref().unwrap()),
+            [
+                ("m".to_string(), SymbolKind::FUNCTION),
+                ("b".to_string(), SymbolKind::MODULE)
+            ]
+        );
+        assert_eq!(
+            symbols[0].selection_range,
+            Range::new(Position::new(0, 9), Position::new(0, 10))
+        );
+        // an unclosed block runs to the end of the template
+        assert_eq!(symbols[1].range.end, Position::new(1, 63));
+    }
+
+    #[test]
+    fn completes_inherited_blocks() {
+        let read = |path: &Path| match path.to_str()? {
+            "/templates/base.html" => Some(
+                r#"{% extends "root.html" %}{% block head %}{% end %}{% block main %}{% end %}"#
+                    .to_string(),
+            ),
+            "/templates/root.html" => {
+                Some("{% block main %}{% end %}{% block footer %}{% end %}".to_string())
+            }
+            _ => None,
+        };
+        let source = r#"{% extends "base.html" %}{% block "#;
+        let items = completion(&project(), source, source.len(), read).unwrap();
+        let labels = items
+            .iter()
+            .map(|item| (item.label.as_str(), item.detail.as_deref().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            labels,
+            [
+                ("head", "base.html"),
+                ("main", "base.html"),
+                ("footer", "root.html")
+            ]
+        );
+
+        // only inside of an unclosed block tag
+        let source = r#"{% extends "base.html" %}{% if "#;
+        assert!(completion(&project(), source, source.len(), read).is_none());
+        let source = r#"{% extends "base.html" %}{% block a %}"#;
+        assert!(completion(&project(), source, source.len(), read).is_none());
+    }
+}
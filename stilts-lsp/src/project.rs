@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use camino::Utf8PathBuf;
+use stilts_codegen::config::Config;
+use stilts_lang::Delims;
+
+use crate::document::{tags, TagKind};
+
+/// The parts of a package config the server needs
+///
+/// The config itself holds syn types which can not be sent between threads.
+pub struct Project {
+    pub template_dir: PathBuf,
+    open: String,
+    close: String,
+}
+
+/// The projects of the templates which were opened, keyed by their manifest
+///
+/// Loading a config runs `cargo metadata` so it is only done again once the
+/// manifest was modified.
+#[derive(Default)]
+pub struct Projects {
+    loaded: Mutex<HashMap<PathBuf, Loaded>>,
+}
+
+// a project as it was loaded, it is `None` when the config could not be loaded
+struct Loaded {
+    modified: Option<SystemTime>,
+    project: Option<Arc<Project>>,
+}
+
+impl Projects {
+    // the project of the closest Cargo.toml above a template
+    pub fn find(&self, path: &Path) -> Arc<Project> {
+        let manifest = path
+            .ancestors()
+            .skip(1)
+            .map(|dir| dir.join("Cargo.toml"))
+            .find(|manifest| manifest.is_file());
+        let Some(manifest) = manifest else {
+            return Arc::new(Project::fallback(path));
+        };
+        let modified = std::fs::metadata(&manifest)
+            .and_then(|meta| meta.modified())
+            .ok();
+
+        let mut loaded = self.loaded.lock().unwrap();
+        let project = match loaded.get(&manifest) {
+            Some(cached) if cached.modified == modified => cached.project.clone(),
+            _ => {
+                let project = Project::load(&manifest).map(Arc::new);
+                let cached = Loaded {
+                    modified,
+                    project: project.clone(),
+                };
+                loaded.insert(manifest, cached);
+                project
+            }
+        };
+        // a manifest which can not be loaded is treated like there is none
+        project.unwrap_or_else(|| Arc::new(Project::fallback(path)))
+    }
+}
+
+impl Project {
+    fn load(manifest: &Path) -> Option<Self> {
+        let manifest = Utf8PathBuf::from_path_buf(manifest.to_path_buf()).ok()?;
+        let config = Config::load_from(&manifest, None).ok()?;
+        Some(Self {
+            template_dir: config.template_dir.into_std_path_buf(),
+            open: config.delimiters.open().to_string(),
+            close: config.delimiters.close().to_string(),
+        })
+    }
+
+    // templates outside of a cargo project use the default config next to them
+    pub fn fallback(path: &Path) -> Self {
+        let delims = Delims::default();
+        Self {
+            template_dir: path.parent().map(Path::to_path_buf).unwrap_or_default(),
+            open: delims.open().to_string(),
+            close: delims.close().to_string(),
+        }
+    }
+
+    pub fn delims(&self) -> Delims {
+        Delims::new(self.open.clone(), self.close.clone())
+    }
+
+    pub fn open(&self) -> &str {
+        &self.open
+    }
+
+    pub fn close(&self) -> &str {
+        &self.close
+    }
+
+    pub fn resolve(&self, name: &str) -> PathBuf {
+        self.template_dir.join(name)
+    }
+
+    // the templates a template extends from its parent upwards, `read` gets the
+    // source of a template so open documents are used over the files on disk
+    pub fn ancestors(
+        &self,
+        source: &str,
+        read: impl Fn(&Path) -> Option<String>,
+    ) -> Vec<(PathBuf, String)> {
+        let mut ancestors: Vec<(PathBuf, String)> = Vec::new();
+        let mut parent = self.parent(source);
+        while let Some(path) = parent {
+            // stop at a cycle, the parser reports those when compiling
+            if ancestors.iter().any(|(p, _)| *p == path) {
+                break;
+            }
+            let Some(source) = read(&path) else {
+                break;
+            };
+            parent = self.parent(&source);
+            ancestors.push((path, source));
+        }
+        ancestors
+    }
+
+    fn parent(&self, source: &str) -> Option<PathBuf> {
+        tags(source, &self.delims())
+            .into_iter()
+            .find_map(|tag| match tag.kind {
+                TagKind::Extends(name) => Some(self.resolve(name.value)),
+                _ => None,
+            })
+    }
+}
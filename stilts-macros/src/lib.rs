@@ -2,26 +2,8 @@
 
 #![forbid(unsafe_code)]
 
-use parse::TemplateInput;
 use proc_macro::TokenStream;
-
-mod config;
-mod expand;
-mod integrations;
-mod parse;
-mod pathing;
-
-pub(crate) const ATTR_NAME: &str = "stilts";
-
-macro_rules! err {
-    ($span:expr, $msg:expr) => {
-        syn::Error::new(syn::spanned::Spanned::span(&$span), $msg)
-    };
-    ($msg:expr) => {
-        syn::Error::new(proc_macro2::Span::call_site(), $msg)
-    };
-}
-pub(crate) use err;
+use stilts_codegen::{expand, parse::TemplateInput};
 
 macro_rules! abort {
     ($e:expr) => {
@@ -31,7 +13,6 @@ macro_rules! abort {
         }
     };
 }
-pub(crate) use abort;
 
 use quote::quote;
 use syn::DeriveInput;
@@ -56,3 +37,12 @@ pub fn derive_template(input: TokenStream) -> TokenStream {
         }
     }
 }
+
+// documented where it is re-exported in the i18n module of the main crate
+#[cfg(feature = "i18n")]
+#[proc_macro]
+pub fn translations(input: TokenStream) -> TokenStream {
+    syn::parse_macro_input!(input as syn::parse::Nothing);
+    let config = abort!(stilts_codegen::config::Config::load());
+    abort!(stilts_codegen::i18n::expand_translations(&config)).into()
+}
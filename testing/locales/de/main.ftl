@@ -0,0 +1,8 @@
+welcome-user = Willkommen, { $name }!
+unread = { $count ->
+    [one] Du hast eine ungelesene Nachricht
+   *[other] Du hast { $count } ungelesene Nachrichten
+}
+login = Anmelden
+    .placeholder = Deine { email }
+email = E-Mail-Adresse
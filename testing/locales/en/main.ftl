@@ -0,0 +1,9 @@
+welcome-user = Welcome, { $name }!
+unread = { $count ->
+    [one] You have one unread message
+   *[other] You have { $count } unread messages
+}
+login = Log in
+    .placeholder = Your { email }
+email = email address
+only-english = Only in english
@@ -0,0 +1,103 @@
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use stilts::async_io::AsyncTemplate;
+use stilts::Template;
+
+#[derive(Template)]
+#[stilts(content = "Hello {% name %}!", trim = false)]
+struct Greeting<'a> {
+    name: &'a str,
+}
+
+#[derive(Template)]
+#[stilts(content = "{% for i in 0..*count %}<p>{% i %}</p>{% end %}", trim = false)]
+struct Paragraphs {
+    count: usize,
+}
+
+/// Records the size of every write it receives
+#[derive(Default)]
+struct CountingWriter {
+    writes: Vec<usize>,
+    fail: bool,
+}
+
+impl tokio::io::AsyncWrite for CountingWriter {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if self.fail {
+            return Poll::Ready(Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed")));
+        }
+        self.writes.push(buf.len());
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[tokio::test]
+async fn write_async_vec() {
+    let mut out = Vec::new();
+    Greeting { name: "World" }
+        .write_async(&mut out)
+        .await
+        .unwrap();
+
+    assert_eq!(out, b"Hello World!");
+}
+
+#[tokio::test]
+async fn write_async_flushes_large_output() {
+    let template = Paragraphs { count: 4096 };
+    let mut writer = CountingWriter::default();
+    template.write_async(&mut writer).await.unwrap();
+
+    assert!(writer.writes.len() > 1);
+    assert_eq!(
+        writer.writes.iter().sum::<usize>(),
+        template.render().unwrap().len()
+    );
+}
+
+#[tokio::test]
+async fn write_async_keeps_error() {
+    let mut writer = CountingWriter {
+        fail: true,
+        ..Default::default()
+    };
+    let err = Greeting { name: "World" }
+        .write_async(&mut writer)
+        .await
+        .unwrap_err();
+
+    assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+}
+
+// generic code can only spawn the future if it is known to be `Send`
+fn spawn_render<T>(template: T) -> tokio::task::JoinHandle<io::Result<Vec<u8>>>
+where
+    T: AsyncTemplate + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut out = Vec::new();
+        template.write_async_send(&mut out).await?;
+        Ok(out)
+    })
+}
+
+#[tokio::test]
+async fn write_async_send() {
+    let out = spawn_render(Paragraphs { count: 2 }).await.unwrap().unwrap();
+    assert_eq!(out, b"<p>0</p><p>1</p>");
+}
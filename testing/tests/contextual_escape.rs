@@ -0,0 +1,135 @@
+use stilts::Template;
+
+#[derive(Template)]
+#[stilts(
+    content = r#"<script>var name = "{% name %}"; var count = {% count %};</script>"#,
+    escape = ::stilts::escaping::Html,
+    trim = false
+)]
+struct Script<'a> {
+    name: &'a str,
+    count: u32,
+}
+
+#[derive(Template)]
+#[stilts(
+    content = r#"<a href="{% link %}" title="{% title %}">search</a><a href="/search?q={% query %}">again</a>"#,
+    escape = ::stilts::escaping::Html
+)]
+struct Links<'a> {
+    link: &'a str,
+    title: &'a str,
+    query: &'a str,
+}
+
+#[derive(Template)]
+#[stilts(
+    content = r#"<p style="font-family: '{% font %}'" onclick="pick('{% font %}')">{% font %}</p>"#,
+    escape = ::stilts::escaping::Html
+)]
+struct Styled<'a> {
+    font: &'a str,
+}
+
+// the html escaper is recognised without the leading `::`
+#[derive(Template)]
+#[stilts(
+    content = r#"<a href="{% link %}">{% link %}</a>"#,
+    escape = stilts::escaping::Html
+)]
+struct RelativePath<'a> {
+    link: &'a str,
+}
+
+#[test]
+fn script_string() {
+    let script = Script {
+        name: "\"</script>",
+        count: 3,
+    };
+
+    assert_eq!(
+        script.render().unwrap(),
+        r#"<script>var name = "\x22\x3c\x2fscript\x3e"; var count = 3;</script>"#
+    );
+}
+
+#[test]
+fn url_attributes() {
+    let links = Links {
+        link: "javascript:alert(1)",
+        title: "\"quoted\"",
+        query: "a b&c",
+    };
+
+    assert_eq!(
+        links.render().unwrap(),
+        r##"<a href="#invalid-url" title="&quot;quoted&quot;">search</a><a href="/search?q=a%20b%26c">again</a>"##
+    );
+}
+
+#[test]
+fn style_and_event_attributes() {
+    let styled = Styled { font: "a'b" };
+
+    assert_eq!(
+        styled.render().unwrap(),
+        r#"<p style="font-family: 'a\27 b'" onclick="pick('a\x27b')">a&#x27;b</p>"#
+    );
+}
+
+#[test]
+fn relative_escaper_path() {
+    let link = RelativePath {
+        link: "javascript:alert(1)",
+    };
+
+    assert_eq!(
+        link.render().unwrap(),
+        r##"<a href="#invalid-url">javascript:alert(1)</a>"##
+    );
+}
+
+// macro bodies are escaped for the context of each call
+#[derive(Template)]
+#[stilts(
+    content = r#"{% macro v(u: &str) %}{% u %}{% end %}<a href="{% call v(value) %}" onclick="f('{% call v(value) %}')" style="content: '{% call v(value) %}'">{% call v(value) %}</a><script>var v = "{% call v(value) %}";</script>"#,
+    escape = ::stilts::escaping::Html,
+    trim = false
+)]
+struct MacroContexts<'a> {
+    value: &'a str,
+}
+
+// regular expressions and template literals in scripts do not confuse the context
+#[derive(Template)]
+#[stilts(
+    content = r#"<script>var q = s.replace(/'/g, "`"); var r = a / b / "{% value %}"; var t = `{% value %}`;</script>"#,
+    escape = ::stilts::escaping::Html,
+    trim = false
+)]
+struct ScriptLiterals<'a> {
+    value: &'a str,
+}
+
+#[test]
+fn macro_call_contexts() {
+    let tmpl = MacroContexts {
+        value: "javascript:alert('x')",
+    };
+
+    assert_eq!(
+        tmpl.render().unwrap(),
+        r##"<a href="#invalid-url" onclick="f('javascript\x3aalert\x28\x27x\x27\x29')" style="content: 'javascript\3a alert\28 \27 x\27 \29 '">javascript:alert(&#x27;x&#x27;)</a><script>var v = "javascript\x3aalert\x28\x27x\x27\x29";</script>"##
+    );
+}
+
+#[test]
+fn script_literals() {
+    let tmpl = ScriptLiterals { value: "'\"`" };
+
+    assert_eq!(
+        tmpl.render().unwrap(),
+        r#"<script>var q = s.replace(/'/g, "`"); var r = a / b / "\x27\x22\x60"; var t = `\x27\x22\x60`;</script>"#
+    );
+}
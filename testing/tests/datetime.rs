@@ -0,0 +1,108 @@
+use std::fmt::Write;
+
+use chrono::{FixedOffset, NaiveDate, TimeZone, Utc};
+use stilts::{DateTimeExt, Template};
+
+#[derive(Template)]
+#[stilts(
+    content = "{% posted.strftime(\"%A %-d %B %Y, %I:%M %p\") %}|{% posted.rfc3339() %}|{% posted.relative(now) %}|{% posted.strftime_in(*zone, \"%F %T %:z\") %}",
+    trim = false
+)]
+struct Post {
+    posted: chrono::DateTime<Utc>,
+    now: chrono::DateTime<Utc>,
+    zone: FixedOffset,
+}
+
+// a type of the user with a `date` method is left alone
+struct Entry;
+
+impl Entry {
+    fn date(&self, fmt: &str) -> String {
+        fmt.replace("%Q", "quarter")
+    }
+}
+
+#[derive(Template)]
+#[stilts(content = "{% entry.date(\"%Q\") %}", trim = false)]
+struct Journal {
+    entry: Entry,
+}
+
+#[test]
+fn chrono_formats() {
+    let post = Post {
+        posted: Utc.with_ymd_and_hms(2024, 3, 5, 14, 7, 9).unwrap(),
+        now: Utc.with_ymd_and_hms(2024, 3, 5, 17, 30, 0).unwrap(),
+        zone: FixedOffset::east_opt(-5 * 3600 - 1800).unwrap(),
+    };
+
+    assert_eq!(
+        post.render().unwrap(),
+        "Tuesday 5 March 2024, 02:07 PM|2024-03-05T14:07:09Z|3 hours ago|2024-03-05 08:37:09 -05:30"
+    );
+}
+
+#[test]
+fn user_date_method() {
+    assert_eq!(Journal { entry: Entry }.render().unwrap(), "quarter");
+}
+
+#[test]
+fn time_formats() {
+    let posted = time::macros::datetime!(2024-01-02 03:04:05.25 +01:00);
+    let later = time::macros::datetime!(2024-01-12 03:04:05 +01:00);
+
+    assert_eq!(posted.rfc3339().to_string(), "2024-01-02T03:04:05.250+01:00");
+    assert_eq!(
+        posted.strftime("%y/%m/%d %j %a").to_string(),
+        "24/01/02 002 Tue"
+    );
+    assert_eq!(later.relative(posted).to_string(), "in 1 week");
+    assert_eq!(
+        posted.strftime_in(time::UtcOffset::UTC, "%H:%M %z").to_string(),
+        "02:04 +0000"
+    );
+}
+
+// every supported specifier renders
+#[derive(Template)]
+#[stilts(
+    content = "{% for day in days.iter() %}{% day.strftime(\"%Y %y %m %d %e %H %I %M %S %f %j %p %b %h %B %a %A %u %w %z %:z %s %F %T %R %D %n %t %% %-d %-H\") %}|{% day.strftime(fmt) %}{% end %}",
+    trim = false
+)]
+struct Specifiers<'a> {
+    days: &'a [time::OffsetDateTime],
+    fmt: &'a str,
+}
+
+#[test]
+fn all_specifiers() {
+    let days = [time::macros::datetime!(2024-02-09 16:05:03 -02:00)];
+    let rendered = Specifiers {
+        days: &days,
+        fmt: "%-I%p",
+    }
+    .render()
+    .unwrap();
+
+    assert_eq!(
+        rendered,
+        "2024 24 02 09  9 16 04 05 03 000000000 040 PM Feb Feb February Fri Friday 5 5 -0200 -02:00 \
+         1707501903 2024-02-09 16:05:03 16:05 02/09/24 \n \t % 9 16|4PM"
+    );
+}
+
+#[test]
+fn naive_values() {
+    let date = NaiveDate::from_ymd_opt(2023, 12, 31).unwrap();
+
+    assert_eq!(
+        date.strftime("%D %e %u %w").to_string(),
+        "12/31/23 31 7 0"
+    );
+    assert_eq!(date.rfc3339().to_string(), "2023-12-31T00:00:00");
+
+    let mut out = String::new();
+    assert!(write!(out, "{}", date.strftime("%Q")).is_err());
+}
@@ -0,0 +1,73 @@
+use stilts::Template;
+
+#[derive(Template)]
+#[stilts(content = "<h1>{% title %}</h1>")]
+struct Heading {
+    title: &'static str,
+}
+
+#[derive(Template)]
+#[stilts(content = "<p>{% count %} items</p>", trim = false)]
+struct Count {
+    count: usize,
+}
+
+#[test]
+fn boxed_widgets() {
+    let widgets: Vec<Box<dyn stilts::DynTemplate>> = vec![
+        Box::new(Heading { title: "Dashboard" }),
+        Box::new(Count { count: 3 }),
+    ];
+
+    assert_eq!(
+        widgets.render().unwrap(),
+        "<h1>Dashboard</h1><p>3 items</p>"
+    );
+}
+
+// `Send` because the web framework integrations need it
+#[derive(Template)]
+#[stilts(
+    content = "<section>{% widget.display() %}</section>",
+    escape = ::stilts::escaping::Html
+)]
+struct Panel {
+    widget: Box<dyn stilts::DynTemplate + Send>,
+}
+
+#[derive(Template)]
+#[stilts(
+    content = "<section>{% widget.display().safe() %}</section>",
+    escape = ::stilts::escaping::Html
+)]
+struct TrustedPanel {
+    widget: Box<dyn stilts::DynTemplate + Send>,
+}
+
+#[test]
+fn nested_dyn_template() {
+    let panel = Panel {
+        widget: Box::new(Count { count: 1 }),
+    };
+    let trusted = TrustedPanel {
+        widget: Box::new(Count { count: 1 }),
+    };
+
+    // the escaper of a boxed template is not known so it is escaped again
+    assert_eq!(
+        panel.render().unwrap(),
+        "<section>&lt;p&gt;1 items&lt;&#x2F;p&gt;</section>"
+    );
+    assert_eq!(trusted.render().unwrap(), "<section><p>1 items</p></section>");
+}
+
+#[test]
+fn optional_template() {
+    let missing: Option<Heading> = None;
+
+    assert_eq!(missing.render().unwrap(), "");
+    assert_eq!(
+        Some(&Heading { title: "Hi" }).render().unwrap(),
+        "<h1>Hi</h1>"
+    );
+}
@@ -0,0 +1,73 @@
+use stilts::Template;
+
+#[derive(Template)]
+#[stilts(path = "page.html.j2")]
+struct Page<'a> {
+    title: &'a str,
+}
+
+#[derive(Template)]
+#[stilts(path = "feed.rss")]
+struct Feed<'a> {
+    title: &'a str,
+}
+
+#[derive(Template)]
+#[stilts(path = "emails/welcome.txt")]
+struct Welcome<'a> {
+    name: &'a str,
+}
+
+#[test]
+fn template_suffix() {
+    let page = Page { title: "<b>" };
+
+    assert_eq!(page.mime_str(), Some("text/html"));
+    assert_eq!(page.render().unwrap(), "<h1>&lt;b&gt;</h1>");
+}
+
+#[test]
+fn mime_type() {
+    let feed = Feed { title: "a & b" };
+
+    assert_eq!(feed.mime_str(), Some("application/rss+xml"));
+    assert_eq!(feed.render().unwrap(), "<title>a &amp; b</title>");
+}
+
+#[test]
+fn glob() {
+    let welcome = Welcome { name: "<i>" };
+
+    assert_eq!(welcome.render().unwrap(), "Hi&lt;i&gt;");
+}
+
+#[derive(Template)]
+#[stilts(path = "emails/receipts/order.txt", trim = false)]
+struct Order<'a> {
+    item: &'a str,
+}
+
+#[derive(Template)]
+#[stilts(path = "commands/run.txt", trim = false)]
+struct Command<'a> {
+    arg: &'a str,
+}
+
+#[derive(Template)]
+#[stilts(path = "commands/nested/run.txt", trim = false)]
+struct NestedCommand<'a> {
+    arg: &'a str,
+}
+
+#[test]
+fn glob_directories() {
+    // `**` matches any number of directories
+    let order = Order { item: "<i>" };
+    assert_eq!(order.render().unwrap(), "Order &lt;i&gt;");
+
+    // `*` does not match across a `/`
+    let command = Command { arg: "a b" };
+    assert_eq!(command.render().unwrap(), "echo 'a b'");
+    let nested = NestedCommand { arg: "a b" };
+    assert_eq!(nested.render().unwrap(), "echo a b");
+}
@@ -0,0 +1,33 @@
+use stilts::escaping::{CssIdent, Escaped, Escaper, Json, Latex, UrlPath, UrlQuery, Xml};
+use stilts::Template;
+
+#[derive(Template)]
+#[stilts(path = "greet.sh", trim = false)]
+struct Greet<'a> {
+    greeting: &'a str,
+}
+
+fn escape(value: &str, escaper: impl Escaper) -> String {
+    Escaped::new(value, escaper).to_string()
+}
+
+#[test]
+fn default_extension() {
+    let val = Greet {
+        greeting: "it's $HOME",
+    }
+    .render()
+    .unwrap();
+
+    assert_eq!(val, "echo 'it'\\''s $HOME'");
+}
+
+#[test]
+fn builtin_escapers() {
+    assert_eq!(escape("a\"b</c>\n", Json), r#"a\"b\u003c/c\u003e\n"#);
+    assert_eq!(escape("a/b c?", UrlPath), "a/b%20c%3F");
+    assert_eq!(escape("a/b c&é", UrlQuery), "a%2Fb%20c%26%C3%A9");
+    assert_eq!(escape("1st.class", CssIdent), r"\31 st\.class");
+    assert_eq!(escape("<a b='c'>", Xml), "&lt;a b=&apos;c&apos;&gt;");
+    assert_eq!(escape("50% of $x_1", Latex), r"50\% of \$x\_1");
+}
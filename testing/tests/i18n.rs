@@ -0,0 +1,94 @@
+use stilts::Template;
+
+stilts::i18n::translations!();
+
+#[derive(Template)]
+#[stilts(
+    content = "{% t \"welcome-user\" { name: name } %}|{% t \"unread\" { count: *count } %}|{% t \"login.placeholder\" %}",
+    escape = ::stilts::escaping::Html,
+    trim = false
+)]
+struct Inbox<'a> {
+    name: &'a str,
+    count: u32,
+}
+
+#[derive(Template)]
+#[stilts(content = "{% t \"welcome-user\" { name } %}|{% t \"only-english\" %}", trim = false)]
+struct Localized<'a> {
+    #[stilts(locale)]
+    locale: String,
+    name: &'a str,
+}
+
+#[derive(Template)]
+#[stilts(
+    content = "{% macro greet(name: &str) %}<b>{% t \"welcome-user\" { name } %}</b>{% end %}{% call greet(name) %}",
+    trim = false
+)]
+struct Greeting<'a> {
+    #[stilts(locale)]
+    locale: &'a str,
+    name: &'a str,
+}
+
+#[test]
+fn default_locale() {
+    let inbox = Inbox {
+        name: "<Ann>",
+        count: 1,
+    };
+    assert_eq!(
+        inbox.render().unwrap(),
+        "Welcome, &lt;Ann&gt;!|You have one unread message|Your email address"
+    );
+}
+
+#[test]
+fn render_in_locale() {
+    let inbox = Inbox {
+        name: "Jo",
+        count: 3,
+    };
+    assert_eq!(
+        inbox.render_locale("de-AT").unwrap(),
+        "Willkommen, Jo!|Du hast 3 ungelesene Nachrichten|Deine E-Mail-Adresse"
+    );
+    assert_eq!(
+        inbox.render().unwrap(),
+        "Welcome, Jo!|You have 3 unread messages|Your email address"
+    );
+}
+
+#[test]
+fn macros_use_template_locale() {
+    let greeting = Greeting {
+        locale: "de",
+        name: "Jo",
+    };
+    assert_eq!(greeting.render().unwrap(), "<b>Willkommen, Jo!</b>");
+    assert_eq!(greeting.render_locale("en").unwrap(), "<b>Welcome, Jo!</b>");
+}
+
+#[test]
+fn stream_keeps_locale() {
+    let localized = Localized {
+        locale: "de".to_string(),
+        name: "Jo",
+    };
+    // streams are rendered on another thread
+    let chunks = localized
+        .into_stream(4)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(chunks.concat(), b"Willkommen, Jo!|Only in english");
+}
+
+#[test]
+fn field_locale_with_fallback() {
+    let localized = Localized {
+        locale: "de".to_string(),
+        name: "Jo",
+    };
+    assert_eq!(localized.render().unwrap(), "Willkommen, Jo!|Only in english");
+}
@@ -0,0 +1,22 @@
+use stilts::Template;
+
+stilts::i18n::translations!();
+
+#[derive(Template)]
+#[stilts(content = "{% t \"welcome-user\" { name } %}", trim = false)]
+struct Welcome<'a> {
+    name: &'a str,
+}
+
+// the translations are only loaded once, so this has its own test binary
+#[test]
+fn load_from_env_dir() {
+    let dir = std::env::temp_dir().join(format!("stilts-i18n-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("en")).unwrap();
+    std::fs::write(dir.join("en/main.ftl"), "welcome-user = Hi { $name }").unwrap();
+    std::env::set_var(stilts::i18n::DIR_VAR, &dir);
+
+    let rendered = Welcome { name: "Ann" }.render().unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(rendered, "Hi Ann");
+}
@@ -0,0 +1,52 @@
+use stilts::Template;
+
+struct City {
+    name: &'static str,
+    country: &'static str,
+}
+
+#[derive(Template)]
+#[stilts(
+    content = "{% names.join_with(\", \") %}|{% for row in names.batch(2) %}[{% row.join_with(\" \") %}]{% end %}|{% for (name, class) in names.cycle_with([\"odd\", \"even\"]) %}{% class %}:{% name %};{% end %}|{% names.first_n(2).count() %}|{% names.join(\"/\") %}",
+    trim = false
+)]
+struct Names {
+    names: Vec<&'static str>,
+}
+
+#[derive(Template)]
+#[stilts(
+    content = "{% for (country, cities) in cities.group_by(|c| c.country) %}{% country %}: {% for city in cities %}{% city.name %};{% end %}.{% end %}",
+    trim = false
+)]
+struct Cities {
+    cities: Vec<City>,
+}
+
+#[test]
+fn join_batch_cycle() {
+    let names = Names {
+        names: vec!["a", "b", "c"],
+    };
+
+    assert_eq!(
+        names.render().unwrap(),
+        "a, b, c|[a b][c]|odd:a;even:b;odd:c;|2|a/b/c"
+    );
+}
+
+#[test]
+fn group_by_first_appearance() {
+    let cities = Cities {
+        cities: vec![
+            City { name: "Paris", country: "France" },
+            City { name: "Berlin", country: "Germany" },
+            City { name: "Lyon", country: "France" },
+        ],
+    };
+
+    assert_eq!(
+        cities.render().unwrap(),
+        "France: Paris;Lyon;.Germany: Berlin;."
+    );
+}
@@ -0,0 +1,53 @@
+use stilts::Template;
+
+#[derive(Template)]
+#[stilts(
+    content = r#"<script>var tags = {% tags.json_script() %};</script><div data-tags="{% tags.json_attr() %}"></div>"#,
+    escape = ::stilts::escaping::Html,
+    trim = false
+)]
+struct Tags {
+    tags: Vec<&'static str>,
+}
+
+#[test]
+fn json_script_and_attr() {
+    let tags = Tags {
+        tags: vec!["</script>", "it's \"quoted\""],
+    };
+
+    assert_eq!(
+        tags.render().unwrap(),
+        concat!(
+            r#"<script>var tags = ["\u003c/script\u003e","it's \"quoted\""];</script>"#,
+            r#"<div data-tags="[&quot;\u003c/script\u003e&quot;,&quot;it&#39;s \&quot;quoted\&quot;&quot;]"></div>"#,
+        )
+    );
+}
+
+// outside of their own part of the template both are escaped like any other value
+#[derive(Template)]
+#[stilts(
+    content = r#"<p>{% tags.json_script() %}</p><div data-tags={% tags.json_attr() %}></div><a title="{% tags.json_script() %}"></a>"#,
+    escape = ::stilts::escaping::Html,
+    trim = false
+)]
+struct Elsewhere {
+    tags: Vec<&'static str>,
+}
+
+#[test]
+fn escaped_elsewhere() {
+    let elsewhere = Elsewhere {
+        tags: vec!["a b", "'"],
+    };
+
+    assert_eq!(
+        elsewhere.render().unwrap(),
+        concat!(
+            r#"<p>[&quot;a b&quot;,&quot;&#x27;&quot;]</p>"#,
+            r#"<div data-tags=&#x5B;&amp;quot&#x3B;a&#x20;b&amp;quot&#x3B;&#x2C;&amp;quot&#x3B;&amp;&#x23;39&#x3B;&amp;quot&#x3B;&#x5D;></div>"#,
+            r#"<a title="[&quot;a b&quot;,&quot;&#x27;&quot;]"></a>"#,
+        )
+    );
+}
@@ -0,0 +1,36 @@
+use stilts::Template;
+
+#[derive(Template)]
+#[stilts(path = "terms.html", locales = ["de"], trim = false)]
+struct Terms<'a> {
+    company: &'a str,
+}
+
+#[test]
+fn variant_for_locale() {
+    let terms = Terms { company: "A&B" };
+    assert_eq!(terms.render_locale("de").unwrap(), "<p>AGB von A&amp;B</p>\n");
+    assert_eq!(terms.render_locale("de-CH").unwrap(), "<p>AGB von A&amp;B</p>\n");
+}
+
+#[test]
+fn fallback_to_base() {
+    let terms = Terms { company: "A&B" };
+    assert_eq!(terms.render().unwrap(), "<p>Terms of A&amp;B</p>\n");
+    assert_eq!(terms.render_locale("fr").unwrap(), "<p>Terms of A&amp;B</p>\n");
+    assert_eq!(terms.render_locale("ja").unwrap(), "<p>Terms of A&amp;B</p>\n");
+}
+
+#[derive(Template)]
+#[stilts(path = "notice.html", locales = ["de"], trim = false)]
+struct Notice;
+
+#[test]
+fn variant_size_hint() {
+    // sized for the shorter variant instead of the base template
+    let notice = Notice.render_locale("de").unwrap();
+    assert_eq!(notice, "Wartung heute\n");
+    assert_eq!(notice.capacity(), notice.len());
+    let notice = Notice.render_locale("fr").unwrap();
+    assert_eq!(notice.capacity(), notice.len());
+}
@@ -0,0 +1,49 @@
+use stilts::{NumberExt, Template};
+
+#[derive(Template)]
+#[stilts(
+    content = "{% total.fixed(2).grouped(\",\") %}|{% total.currency(\"$\") %}|{% share.percent() %}|{% size.filesize() %}|{% place.ordinal() %}",
+    trim = false
+)]
+struct Report {
+    total: f64,
+    share: f32,
+    size: u64,
+    place: i32,
+}
+
+#[test]
+fn report() {
+    let report = Report {
+        total: -1234567.891,
+        share: 0.256,
+        size: 1_258_291,
+        place: 22,
+    };
+
+    assert_eq!(
+        report.render().unwrap(),
+        "-1,234,567.89|-$1,234,567.89|25.6%|1.2 MiB|22nd"
+    );
+}
+
+#[test]
+fn edge_cases() {
+    assert_eq!(1000.grouped(" ").to_string(), "1 000");
+    assert_eq!(999.grouped(",").to_string(), "999");
+    assert_eq!(12.fixed(2).to_string(), "12.00");
+    assert_eq!(512.filesize().to_string(), "512 B");
+    assert_eq!(113.ordinal().to_string(), "113th");
+    assert_eq!(u128::MAX.ordinal().to_string(), format!("{}th", u128::MAX));
+    assert_eq!((-0.001).currency("€").to_string(), "€0.00");
+}
+
+#[test]
+fn large_integers_are_exact() {
+    let amount = 9_007_199_254_740_993_i64;
+    assert_eq!(amount.currency("$").to_string(), "$9,007,199,254,740,993.00");
+    assert_eq!((-amount).currency("$").to_string(), "-$9,007,199,254,740,993.00");
+    assert_eq!(u128::MAX.percent().to_string(), format!("{}00%", u128::MAX));
+    assert_eq!((-3).percent().to_string(), "-300%");
+    assert_eq!(0u8.percent().to_string(), "0%");
+}
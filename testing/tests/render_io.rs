@@ -0,0 +1,56 @@
+use std::io;
+
+use stilts::Template;
+
+#[derive(Template)]
+#[stilts(content = "Hello {% name %}!", trim = false)]
+struct Greeting<'a> {
+    name: &'a str,
+}
+
+struct FailingWriter;
+
+impl io::Write for FailingWriter {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn render_bytes() {
+    let val = Greeting { name: "Wörld" }.render_bytes().unwrap();
+
+    assert_eq!(val, "Hello Wörld!".as_bytes());
+}
+
+#[test]
+fn write_to_vec() {
+    let mut out = Vec::new();
+    Greeting { name: "World" }.write_to(&mut out).unwrap();
+
+    assert_eq!(out, b"Hello World!");
+}
+
+#[test]
+fn write_to_keeps_error() {
+    let err = Greeting { name: "World" }
+        .write_to(&mut FailingWriter)
+        .unwrap_err();
+
+    assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+    assert_eq!(err.to_string(), "closed");
+}
+
+#[test]
+fn append_bytes() {
+    let mut out = b"> ".to_vec();
+    Greeting { name: "World" }
+        .fmt(&mut stilts::io::ByteWriter(&mut out))
+        .unwrap();
+
+    assert_eq!(out, b"> Hello World!");
+}
@@ -0,0 +1,65 @@
+use stilts::escaping::SafeString;
+use stilts::Template;
+
+#[derive(Template)]
+#[stilts(content = "<b>{% name %}</b>", escape = ::stilts::escaping::Html)]
+struct Child<'a> {
+    name: &'a str,
+}
+
+#[derive(Template)]
+#[stilts(content = "<div>{% child.display() %}</div>", escape = ::stilts::escaping::Html)]
+struct Parent<'a> {
+    child: Child<'a>,
+}
+
+// a template without escaping is not trusted by one which escapes html
+#[derive(Template)]
+#[stilts(content = "<b>{% name %}</b>", escape = ::stilts::escaping::Empty)]
+struct Unescaped<'a> {
+    name: &'a str,
+}
+
+#[derive(Template)]
+#[stilts(content = "<div>{% child.display() %}</div>", escape = ::stilts::escaping::Html)]
+struct Wrapper<'a> {
+    child: Unescaped<'a>,
+}
+
+#[derive(Template)]
+#[stilts(content = "{% trusted %}{% untrusted %}", escape = ::stilts::escaping::Html)]
+struct Mixed {
+    trusted: SafeString,
+    untrusted: String,
+}
+
+#[test]
+fn nested_template_not_escaped() {
+    let parent = Parent {
+        child: Child { name: "<i>" },
+    };
+
+    assert_eq!(parent.render().unwrap(), "<div><b>&lt;i&gt;</b></div>");
+}
+
+#[test]
+fn less_escaped_template_escaped() {
+    let wrapper = Wrapper {
+        child: Unescaped { name: "<i>" },
+    };
+
+    assert_eq!(
+        wrapper.render().unwrap(),
+        "<div>&lt;b&gt;&lt;i&gt;&lt;&#x2F;b&gt;</div>"
+    );
+}
+
+#[test]
+fn safe_string_not_escaped() {
+    let mixed = Mixed {
+        trusted: SafeString::new("<br>"),
+        untrusted: "<br>".to_string(),
+    };
+
+    assert_eq!(mixed.render().unwrap(), "<br>&lt;br&gt;");
+}
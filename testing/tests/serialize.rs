@@ -0,0 +1,44 @@
+use std::collections::BTreeMap;
+
+use stilts::Template;
+
+#[derive(Template)]
+#[stilts(content = "{% data.json_pretty() %}")]
+struct Pretty {
+    data: Vec<u32>,
+}
+
+#[derive(Template)]
+#[stilts(content = "{% data.yaml() %}")]
+struct YamlData {
+    data: BTreeMap<&'static str, String>,
+}
+
+#[derive(Template)]
+#[stilts(content = "{% data.toml() %}")]
+struct TomlData {
+    data: BTreeMap<&'static str, u32>,
+}
+
+#[test]
+fn json_pretty() {
+    let val = Pretty { data: vec![1, 2] }.render().unwrap();
+
+    assert_eq!(val, "[\n  1,\n  2\n]");
+}
+
+#[test]
+fn yaml() {
+    let data = BTreeMap::from([("name", "Zoë".to_string())]);
+    let val = YamlData { data }.render().unwrap();
+
+    assert_eq!(val, "name: Zoë\n");
+}
+
+#[test]
+fn toml() {
+    let data = BTreeMap::from([("a", 1), ("b", 2)]);
+    let val = TomlData { data }.render().unwrap();
+
+    assert_eq!(val, "a = 1\nb = 2\n");
+}
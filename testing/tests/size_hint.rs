@@ -0,0 +1,50 @@
+use stilts::Template;
+
+#[derive(Template)]
+#[stilts(content = "Literal {% a %} Template", trim = false)]
+struct LitTemplate<'a> {
+    a: &'a str,
+}
+
+#[derive(Template)]
+#[stilts(
+    content = "<ul>{% for i in items %}<li>{% i %}</li>{% end %}</ul>",
+    trim = false
+)]
+struct HintedTemplate {
+    #[stilts(size_hint = |items: &Vec<u32>| items.len() * 10)]
+    items: Vec<u32>,
+}
+
+#[derive(Template)]
+#[stilts(path = "sample.html")]
+struct InheritedTemplate<'a> {
+    a: &'a str,
+}
+
+#[test]
+fn static_content() {
+    let val = LitTemplate { a: "cool" };
+
+    assert_eq!(val.size_hint(), "Literal  Template".len());
+}
+
+#[test]
+fn field_hook() {
+    let val = HintedTemplate {
+        items: vec![1, 2, 3],
+    };
+
+    assert_eq!(val.size_hint(), "<ul></ul>".len() + 30);
+    assert_eq!(val.size_hint(), val.render().unwrap().len());
+}
+
+#[test]
+fn lower_bound() {
+    let val = InheritedTemplate {
+        a: "my code content <a></a>",
+    };
+
+    assert!(val.size_hint() > 0);
+    assert!(val.size_hint() <= val.render().unwrap().len());
+}
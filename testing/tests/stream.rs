@@ -0,0 +1,157 @@
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::Duration;
+
+use stilts::Template;
+
+// streamed by the web framework integrations, which makes the feature builds check them,
+// warp can not stream responses
+#[derive(Template)]
+#[stilts(path = "big-table.html")]
+#[cfg_attr(not(feature = "warp"), stilts(stream = 256))]
+struct BigTable {
+    table: Vec<Vec<usize>>,
+}
+
+fn table(size: usize) -> BigTable {
+    BigTable {
+        table: (0..size).map(|_| (0..size).collect()).collect(),
+    }
+}
+
+// writes a full chunk every time it is displayed and counts how often that happened
+struct Tick(Arc<AtomicUsize>);
+
+impl fmt::Display for Tick {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        f.write_str(&"x".repeat(64))
+    }
+}
+
+#[derive(Template)]
+#[stilts(content = "{% for _ in 0..*rows %}{% tick %}{% end %}")]
+struct Ticks {
+    rows: usize,
+    tick: Tick,
+    // disconnects once the template has been dropped by the renderer
+    #[stilts(ignore)]
+    _done: mpsc::Sender<()>,
+}
+
+struct Explode;
+
+impl fmt::Display for Explode {
+    fn fmt(&self, _: &mut fmt::Formatter<'_>) -> fmt::Result {
+        panic!("failed to display")
+    }
+}
+
+#[derive(Template)]
+#[stilts(content = "{% head %}{% explode %}")]
+struct Panics {
+    head: String,
+    explode: Explode,
+}
+
+#[test]
+fn chunks_match_render() {
+    let expected = table(32).render().unwrap();
+
+    let chunks = table(32)
+        .into_stream(256)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    assert!(chunks.len() > 1);
+    assert!(chunks[..chunks.len() - 1].iter().all(|c| c.len() >= 256));
+    assert_eq!(chunks.concat(), expected.as_bytes());
+}
+
+#[test]
+fn dropped_stream_stops() {
+    let count = Arc::new(AtomicUsize::new(0));
+    let (done, finished) = mpsc::channel();
+    let ticks = Ticks {
+        rows: 100_000,
+        tick: Tick(Arc::clone(&count)),
+        _done: done,
+    };
+    let mut stream = ticks.into_stream(64);
+
+    assert!(stream.next().unwrap().is_ok());
+    drop(stream);
+
+    // the renderer only gets a few chunks ahead before it notices the stream is gone
+    assert_eq!(
+        finished.recv_timeout(Duration::from_secs(10)),
+        Err(RecvTimeoutError::Disconnected)
+    );
+    let stopped = count.load(Ordering::SeqCst);
+    assert!(stopped < 5, "rendered {stopped} chunks");
+}
+
+#[test]
+fn panic_ends_with_error() {
+    let panics = Panics {
+        head: "x".repeat(64),
+        explode: Explode,
+    };
+    let chunks = panics.into_stream(16).collect::<Vec<_>>();
+
+    assert!(chunks[0].is_ok());
+    assert!(chunks.last().unwrap().is_err());
+}
+
+#[test]
+#[should_panic = "must not be zero"]
+fn zero_chunk_size() {
+    let _ = table(1).into_stream(0);
+}
+
+#[test]
+fn more_streams_than_threads() {
+    let expected = table(8).render().unwrap();
+    let streams = (0..64)
+        .map(|_| table(8).into_stream(64))
+        .collect::<Vec<_>>();
+
+    for stream in streams {
+        let chunks = stream.collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(chunks.concat(), expected.as_bytes());
+    }
+}
+
+#[test]
+fn streams_beyond_the_thread_limit_wait() {
+    let counts = (0..stilts::stream::MAX_THREADS + 8)
+        .map(|_| Arc::new(AtomicUsize::new(0)))
+        .collect::<Vec<_>>();
+    let streams = counts
+        .iter()
+        .map(|count| {
+            let ticks = Ticks {
+                rows: 4,
+                tick: Tick(Arc::clone(count)),
+                _done: mpsc::channel().0,
+            };
+            ticks.into_stream(64)
+        })
+        .collect::<Vec<_>>();
+
+    // nothing reads the streams so every started one blocks its thread
+    std::thread::sleep(Duration::from_millis(500));
+    let started = counts
+        .iter()
+        .filter(|count| count.load(Ordering::SeqCst) > 0)
+        .count();
+    assert!(started <= stilts::stream::MAX_THREADS, "{started} streams started");
+
+    // the waiting streams run once the ones before them finish
+    for stream in streams {
+        let chunks = stream.collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(chunks.len(), 4);
+    }
+}
@@ -0,0 +1,116 @@
+use stilts::{DisplayExt, Template};
+
+#[derive(Template)]
+#[stilts(
+    content = "{% title.title() %}|{% title.truncate_chars(9) %}|{% title.slugify() %}|{% title.replace_text(\"the\", \"a\") %}",
+    trim = false
+)]
+struct Headline<'a> {
+    title: &'a str,
+}
+
+#[derive(Template)]
+#[stilts(
+    content = "<div>{% body.linebreaks() %}</div><p>{% body.striptags().trim_text().capitalize() %}</p>",
+    escape = ::stilts::escaping::Html,
+    trim = false
+)]
+struct Post<'a> {
+    body: &'a str,
+}
+
+#[derive(Template)]
+#[stilts(
+    content = "<div title={% body.linebreaks() %}></div>",
+    escape = ::stilts::escaping::Html,
+    trim = false
+)]
+struct UnquotedAttr<'a> {
+    body: &'a str,
+}
+
+#[derive(Template)]
+#[stilts(
+    content = "echo {% body.linebreaks() %}",
+    escape = ::stilts::escaping::Shell,
+    trim = false
+)]
+struct Command<'a> {
+    body: &'a str,
+}
+
+// the methods of `str` are not shadowed by the filters
+#[derive(Template)]
+#[stilts(
+    content = "{% if name.trim().is_empty() %}anonymous{% else %}{% name.replace(' ', \"_\") %}{% end %}",
+    trim = false
+)]
+struct Owned {
+    name: String,
+}
+
+#[test]
+fn text_filters() {
+    let headline = Headline {
+        title: "the QUICK brown fox's den",
+    };
+
+    assert_eq!(
+        headline.render().unwrap(),
+        "The Quick Brown Fox's Den|the QUICK...|the-quick-brown-fox-s-den|a QUICK brown fox's den"
+    );
+}
+
+#[test]
+fn linebreaks_and_striptags() {
+    let post = Post {
+        body: "  <b>one</b> & two\nthree\n\nfour ",
+    };
+
+    assert_eq!(
+        post.render().unwrap(),
+        "<div><p>  &lt;b&gt;one&lt;&#x2F;b&gt; &amp; two<br>three</p><p>four </p></div>\
+         <p>One &amp; two\nthree\n\nfour</p>"
+    );
+}
+
+#[test]
+fn linebreaks_only_safe_in_html() {
+    let attr = UnquotedAttr {
+        body: "x onclick=alert(1)",
+    };
+    let command = Command {
+        body: "a; rm -rf /",
+    };
+
+    assert_eq!(
+        attr.render().unwrap(),
+        "<div title=&lt;p&gt;x&#x20;onclick&#x3D;alert&#x28;1&#x29;&lt;&#x2F;p&gt;></div>"
+    );
+    assert_eq!(command.render().unwrap(), "echo '<p>a; rm -rf &#x2F;</p>'");
+}
+
+#[test]
+fn layout_filters() {
+    let text = "one two three four\nfive";
+
+    assert_eq!(
+        text.wordwrap(9).to_string(),
+        "one two\nthree\nfour\nfive"
+    );
+    assert_eq!("a\n\nb\nc".indent(2).to_string(), "a\n\n  b\n  c");
+    assert_eq!("a b/c?".urlencode().to_string(), "a%20b%2Fc%3F");
+}
+
+#[test]
+fn string_methods() {
+    let blank = Owned {
+        name: "  ".to_string(),
+    };
+    let named = Owned {
+        name: "ada lovelace".to_string(),
+    };
+
+    assert_eq!(blank.render().unwrap(), "anonymous");
+    assert_eq!(named.render().unwrap(), "ada_lovelace");
+}